@@ -0,0 +1,677 @@
+//! UCAN-inspired capability tokens for authorizing `create_task`.
+//!
+//! A [`SignedCapabilityToken`] names an issuer (the account/key granted
+//! rights), an audience (this Worker), a validity window, and a set of
+//! [`Capability`] grants like `{ resource: "bundle:com.x.y", ability:
+//! "download", version: "*" }`. A token may carry a `proof` — a parent token
+//! whose audience is this token's issuer — so a service can delegate
+//! narrowly-scoped, short-lived tokens without sharing the root account's
+//! signing key. Verification walks the proof chain, checking signatures and
+//! that every delegated token only attenuates (never broadens) its parent's
+//! grants.
+//!
+//! Signing/verification uses HMAC-SHA256 over a canonical encoding of the
+//! token, keyed by a per-issuer secret the caller resolves (e.g. from KV) —
+//! this module only deals in keys-as-bytes, not where they're stored.
+
+use crate::integrity::Sha256;
+use serde::{Deserialize, Serialize};
+
+/// A single granted right: `ability` on `resource`, optionally scoped to one
+/// `version` (`"*"` matches any version).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capability {
+  pub resource: String,
+  pub ability: String,
+  pub version: String,
+}
+
+impl Capability {
+  /// Whether this capability covers the given request.
+  pub fn covers(&self, resource: &str, ability: &str, version: &str) -> bool {
+    self.resource == resource && self.ability == ability && (self.version == "*" || self.version == version)
+  }
+}
+
+/// A signed capability token, optionally delegated from a parent via `proof`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedCapabilityToken {
+  pub issuer: String,
+  pub audience: String,
+  pub not_before: u64,
+  pub expires_at: u64,
+  pub capabilities: Vec<Capability>,
+  pub signature: [u8; 32],
+  pub proof: Option<Box<SignedCapabilityToken>>,
+}
+
+/// The canonical byte encoding a signature is computed over — every field
+/// except `signature`, plus the `proof`'s signature (but none of the proof's
+/// other fields), which binds a delegated token to that exact parent while
+/// each token in the chain is still signed independently by its own issuer.
+fn canonical_payload(token: &SignedCapabilityToken) -> Vec<u8> {
+  let mut buf = Vec::new();
+  buf.extend_from_slice(token.issuer.as_bytes());
+  buf.push(0);
+  buf.extend_from_slice(token.audience.as_bytes());
+  buf.push(0);
+  buf.extend_from_slice(&token.not_before.to_be_bytes());
+  buf.extend_from_slice(&token.expires_at.to_be_bytes());
+  for cap in &token.capabilities {
+    buf.extend_from_slice(cap.resource.as_bytes());
+    buf.push(b':');
+    buf.extend_from_slice(cap.ability.as_bytes());
+    buf.push(b':');
+    buf.extend_from_slice(cap.version.as_bytes());
+    buf.push(0);
+  }
+  if let Some(proof) = &token.proof {
+    buf.extend_from_slice(&proof.signature);
+  }
+  buf
+}
+
+/// Sign a token's canonical payload with the issuer's secret key.
+pub fn sign_token(key: &[u8], token: &SignedCapabilityToken) -> [u8; 32] {
+  hmac_sha256(key, &canonical_payload(token))
+}
+
+/// Look up the signing key for an issuer. Callers resolve this however they
+/// store per-issuer secrets (e.g. a KV namespace); this module never touches
+/// storage directly.
+pub trait KeyResolver {
+  fn key_for_issuer(&self, issuer: &str) -> Option<Vec<u8>>;
+}
+
+impl<F: Fn(&str) -> Option<Vec<u8>>> KeyResolver for F {
+  fn key_for_issuer(&self, issuer: &str) -> Option<Vec<u8>> {
+    self(issuer)
+  }
+}
+
+/// Collect every issuer named in a token's delegation chain, root first.
+pub fn collect_issuers(token: &SignedCapabilityToken, out: &mut Vec<String>) {
+  if let Some(proof) = &token.proof {
+    collect_issuers(proof, out);
+  }
+  out.push(token.issuer.clone());
+}
+
+/// Verify a token's signature chain, expiry window, and delegation
+/// constraints against `now_secs`, without checking any specific capability.
+pub fn verify_token(
+  token: &SignedCapabilityToken,
+  now_secs: u64,
+  expected_audience: &str,
+  keys: &impl KeyResolver,
+) -> Result<(), String> {
+  if token.audience != expected_audience {
+    return Err("token audience does not match this service".into());
+  }
+  if now_secs < token.not_before {
+    return Err("token is not yet valid".into());
+  }
+  if now_secs >= token.expires_at {
+    return Err("token has expired".into());
+  }
+
+  let key = keys
+    .key_for_issuer(&token.issuer)
+    .ok_or_else(|| format!("unknown issuer: {}", token.issuer))?;
+  let expected_sig = sign_token(&key, token);
+  if !constant_time_eq(&expected_sig, &token.signature) {
+    return Err("token signature is invalid".into());
+  }
+
+  if let Some(proof) = &token.proof {
+    if proof.audience != token.issuer {
+      return Err("delegation chain broken: proof's audience does not match issuer".into());
+    }
+    verify_token(proof, now_secs, &proof.audience, keys)?;
+
+    for cap in &token.capabilities {
+      let covered = proof
+        .capabilities
+        .iter()
+        .any(|p| p.covers(&cap.resource, &cap.ability, &cap.version));
+      if !covered {
+        return Err(format!(
+          "delegated capability {}:{}:{} exceeds what the proof grants",
+          cap.resource, cap.ability, cap.version
+        ));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Verify the token and confirm it grants `ability` on `resource`/`version`.
+pub fn authorize(
+  token: &SignedCapabilityToken,
+  now_secs: u64,
+  expected_audience: &str,
+  keys: &impl KeyResolver,
+  resource: &str,
+  ability: &str,
+  version: &str,
+) -> Result<(), String> {
+  verify_token(token, now_secs, expected_audience, keys)?;
+
+  let granted = token
+    .capabilities
+    .iter()
+    .any(|c| c.covers(resource, ability, version));
+  if !granted {
+    return Err(format!(
+      "token does not grant {ability} on {resource} ({version})"
+    ));
+  }
+
+  Ok(())
+}
+
+/// Build the `resource` string for a bundle, e.g. `bundle:com.x.y`.
+pub fn bundle_resource(bundle_id: &str) -> String {
+  format!("bundle:{bundle_id}")
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+  let mut diff = 0u8;
+  for (x, y) in a.iter().zip(b.iter()) {
+    diff |= x ^ y;
+  }
+  diff == 0
+}
+
+/// HMAC-SHA256 over `data`, keyed by `key` (RFC 2104).
+pub fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+  const BLOCK_SIZE: usize = 64;
+
+  let mut key_block = [0u8; BLOCK_SIZE];
+  if key.len() > BLOCK_SIZE {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    key_block[..32].copy_from_slice(&hasher.finalize());
+  } else {
+    key_block[..key.len()].copy_from_slice(key);
+  }
+
+  let mut ipad = [0x36u8; BLOCK_SIZE];
+  let mut opad = [0x5cu8; BLOCK_SIZE];
+  for i in 0..BLOCK_SIZE {
+    ipad[i] ^= key_block[i];
+    opad[i] ^= key_block[i];
+  }
+
+  let mut inner = Sha256::new();
+  inner.update(&ipad);
+  inner.update(data);
+  let inner_digest = inner.finalize();
+
+  let mut outer = Sha256::new();
+  outer.update(&opad);
+  outer.update(&inner_digest);
+  outer.finalize()
+}
+
+// --- Wire format ---
+//
+// The in-memory [`SignedCapabilityToken`] keeps its signature as raw bytes
+// and nests proofs by value; over the wire (an `Authorization` header) we
+// use hex for the signature and a JSON-friendly shape instead.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityWire {
+  pub resource: String,
+  pub ability: String,
+  pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityTokenWire {
+  pub issuer: String,
+  pub audience: String,
+  pub not_before: u64,
+  pub expires_at: u64,
+  pub capabilities: Vec<CapabilityWire>,
+  pub signature: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub proof: Option<Box<CapabilityTokenWire>>,
+}
+
+impl CapabilityTokenWire {
+  /// Parse into the verifiable in-memory representation.
+  pub fn into_signed(self) -> Result<SignedCapabilityToken, String> {
+    Ok(SignedCapabilityToken {
+      issuer: self.issuer,
+      audience: self.audience,
+      not_before: self.not_before,
+      expires_at: self.expires_at,
+      capabilities: self
+        .capabilities
+        .into_iter()
+        .map(|c| Capability {
+          resource: c.resource,
+          ability: c.ability,
+          version: c.version,
+        })
+        .collect(),
+      signature: hex_decode_32(&self.signature)?,
+      proof: self.proof.map(|p| p.into_signed()).transpose()?.map(Box::new),
+    })
+  }
+}
+
+impl From<&SignedCapabilityToken> for CapabilityTokenWire {
+  fn from(token: &SignedCapabilityToken) -> Self {
+    Self {
+      issuer: token.issuer.clone(),
+      audience: token.audience.clone(),
+      not_before: token.not_before,
+      expires_at: token.expires_at,
+      capabilities: token
+        .capabilities
+        .iter()
+        .map(|c| CapabilityWire {
+          resource: c.resource.clone(),
+          ability: c.ability.clone(),
+          version: c.version.clone(),
+        })
+        .collect(),
+      signature: token.signature.iter().map(|b| format!("{b:02x}")).collect(),
+      proof: token.proof.as_deref().map(|p| Box::new(CapabilityTokenWire::from(p))),
+    }
+  }
+}
+
+fn hex_decode_32(value: &str) -> Result<[u8; 32], String> {
+  if value.len() != 64 {
+    return Err("signature must be 64 hex characters".into());
+  }
+  let mut out = [0u8; 32];
+  for i in 0..32 {
+    out[i] = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)
+      .map_err(|_| "signature is not valid hex".to_string())?;
+  }
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn resolver(pairs: &'static [(&'static str, &'static [u8])]) -> impl KeyResolver {
+    move |issuer: &str| {
+      pairs
+        .iter()
+        .find(|(name, _)| *name == issuer)
+        .map(|(_, key)| key.to_vec())
+    }
+  }
+
+  fn signed(
+    issuer: &str,
+    audience: &str,
+    not_before: u64,
+    expires_at: u64,
+    capabilities: Vec<Capability>,
+    proof: Option<Box<SignedCapabilityToken>>,
+    key: &[u8],
+  ) -> SignedCapabilityToken {
+    let mut token = SignedCapabilityToken {
+      issuer: issuer.into(),
+      audience: audience.into(),
+      not_before,
+      expires_at,
+      capabilities,
+      signature: [0u8; 32],
+      proof,
+    };
+    token.signature = sign_token(key, &token);
+    token
+  }
+
+  #[test]
+  fn test_hmac_sha256_matches_rfc2104_vector() {
+    // RFC 4231 test case 1 (HMAC-SHA-256)
+    let key = [0x0bu8; 20];
+    let data = b"Hi There";
+    let mac = hmac_sha256(&key, data);
+    let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(
+      hex,
+      "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+    );
+  }
+
+  #[test]
+  fn test_authorize_valid_token() {
+    let key = b"root-secret";
+    let token = signed(
+      "alice",
+      "asspp-worker",
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: bundle_resource("com.x.y"),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      key,
+    );
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&token, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "1.0").is_ok());
+  }
+
+  #[test]
+  fn test_authorize_rejects_wrong_audience() {
+    let key = b"root-secret";
+    let token = signed("alice", "someone-else", 0, 1_000_000, vec![], None, key);
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&token, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "1.0").is_err());
+  }
+
+  #[test]
+  fn test_authorize_rejects_expired_token() {
+    let key = b"root-secret";
+    let token = signed(
+      "alice",
+      "asspp-worker",
+      0,
+      100,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      key,
+    );
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&token, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "1.0").is_err());
+  }
+
+  #[test]
+  fn test_authorize_rejects_not_yet_valid() {
+    let key = b"root-secret";
+    let token = signed(
+      "alice",
+      "asspp-worker",
+      1000,
+      2000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      key,
+    );
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&token, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "1.0").is_err());
+  }
+
+  #[test]
+  fn test_authorize_rejects_tampered_signature() {
+    let key = b"root-secret";
+    let mut token = signed(
+      "alice",
+      "asspp-worker",
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      key,
+    );
+    token.signature[0] ^= 0xff;
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&token, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "1.0").is_err());
+  }
+
+  #[test]
+  fn test_authorize_rejects_capability_not_granted() {
+    let key = b"root-secret";
+    let token = signed(
+      "alice",
+      "asspp-worker",
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "1.0".into(),
+      }],
+      None,
+      key,
+    );
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&token, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "2.0").is_err());
+  }
+
+  #[test]
+  fn test_delegation_chain_verifies() {
+    let root_key = b"root-secret";
+    let delegate_key = b"delegate-secret";
+
+    let root = signed(
+      "root-account",
+      "asspp-worker",
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      root_key,
+    );
+
+    let delegated = signed(
+      "delegate-service",
+      "asspp-worker",
+      0,
+      1000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "1.0".into(),
+      }],
+      Some(Box::new(signed(
+        "root-account",
+        "delegate-service",
+        0,
+        1_000_000,
+        vec![Capability {
+          resource: "bundle:com.x.y".into(),
+          ability: "download".into(),
+          version: "*".into(),
+        }],
+        None,
+        root_key,
+      ))),
+      delegate_key,
+    );
+    let _ = root; // root token itself isn't re-checked here; its proof copy is.
+
+    let keys = resolver(&[
+      ("root-account", b"root-secret"),
+      ("delegate-service", b"delegate-secret"),
+    ]);
+    assert!(authorize(
+      &delegated,
+      500,
+      "asspp-worker",
+      &keys,
+      "bundle:com.x.y",
+      "download",
+      "1.0"
+    )
+    .is_ok());
+  }
+
+  #[test]
+  fn test_delegation_rejects_broken_audience_chain() {
+    let root_key = b"root-secret";
+    let delegate_key = b"delegate-secret";
+
+    let bad_proof = signed(
+      "root-account",
+      "someone-else", // should have been "delegate-service"
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      root_key,
+    );
+
+    let delegated = signed(
+      "delegate-service",
+      "asspp-worker",
+      0,
+      1000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "1.0".into(),
+      }],
+      Some(Box::new(bad_proof)),
+      delegate_key,
+    );
+
+    let keys = resolver(&[
+      ("root-account", b"root-secret"),
+      ("delegate-service", b"delegate-secret"),
+    ]);
+    assert!(authorize(
+      &delegated,
+      500,
+      "asspp-worker",
+      &keys,
+      "bundle:com.x.y",
+      "download",
+      "1.0"
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_delegation_rejects_capability_broader_than_proof() {
+    let root_key = b"root-secret";
+    let delegate_key = b"delegate-secret";
+
+    let proof = signed(
+      "root-account",
+      "delegate-service",
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "1.0".into(), // narrow grant
+      }],
+      None,
+      root_key,
+    );
+
+    let delegated = signed(
+      "delegate-service",
+      "asspp-worker",
+      0,
+      1000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(), // tries to broaden to all versions
+      }],
+      Some(Box::new(proof)),
+      delegate_key,
+    );
+
+    let keys = resolver(&[
+      ("root-account", b"root-secret"),
+      ("delegate-service", b"delegate-secret"),
+    ]);
+    assert!(authorize(
+      &delegated,
+      500,
+      "asspp-worker",
+      &keys,
+      "bundle:com.x.y",
+      "download",
+      "1.0"
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn test_wire_roundtrip_preserves_verifiability() {
+    let key = b"root-secret";
+    let token = signed(
+      "alice",
+      "asspp-worker",
+      0,
+      1_000_000,
+      vec![Capability {
+        resource: "bundle:com.x.y".into(),
+        ability: "download".into(),
+        version: "*".into(),
+      }],
+      None,
+      key,
+    );
+
+    let wire = CapabilityTokenWire::from(&token);
+    let json = serde_json::to_string(&wire).unwrap();
+    let parsed: CapabilityTokenWire = serde_json::from_str(&json).unwrap();
+    let restored = parsed.into_signed().unwrap();
+
+    assert_eq!(restored, token);
+    let keys = resolver(&[("alice", b"root-secret")]);
+    assert!(authorize(&restored, 500, "asspp-worker", &keys, "bundle:com.x.y", "download", "1.0").is_ok());
+  }
+
+  #[test]
+  fn test_hex_decode_rejects_bad_length() {
+    let bad = CapabilityTokenWire {
+      issuer: "alice".into(),
+      audience: "asspp-worker".into(),
+      not_before: 0,
+      expires_at: 1000,
+      capabilities: vec![],
+      signature: "deadbeef".into(),
+      proof: None,
+    };
+    assert!(bad.into_signed().is_err());
+  }
+
+  #[test]
+  fn test_collect_issuers_root_first() {
+    let root_key = b"root-secret";
+    let proof = signed("root-account", "delegate-service", 0, 1_000_000, vec![], None, root_key);
+    let token = signed(
+      "delegate-service",
+      "asspp-worker",
+      0,
+      1000,
+      vec![],
+      Some(Box::new(proof)),
+      b"delegate-secret",
+    );
+    let mut issuers = Vec::new();
+    collect_issuers(&token, &mut issuers);
+    assert_eq!(issuers, vec!["root-account".to_string(), "delegate-service".to_string()]);
+  }
+}
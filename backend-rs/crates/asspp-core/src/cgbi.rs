@@ -0,0 +1,613 @@
+//! Deoptimize Apple's "CgBI" PNGs (the pngcrush variant used for app icons)
+//! back into standard PNGs that any viewer can decode. No external
+//! compression crate is available in this workspace, so DEFLATE/zlib and the
+//! PNG chunk framing are hand-rolled here, the same way `integrity` hand-rolls
+//! SHA-256/MD5.
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a];
+
+// --- CRC32 (ISO 3309, used by PNG chunks) ---
+
+fn crc32(data: &[u8]) -> u32 {
+  let mut crc: u32 = 0xffff_ffff;
+  for &byte in data {
+    crc ^= byte as u32;
+    for _ in 0..8 {
+      let mask = (crc & 1).wrapping_neg();
+      crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+    }
+  }
+  !crc
+}
+
+// --- Adler-32 (RFC 1950, the zlib checksum) ---
+
+fn adler32(data: &[u8]) -> u32 {
+  const MOD_ADLER: u32 = 65521;
+  let mut a: u32 = 1;
+  let mut b: u32 = 0;
+  for &byte in data {
+    a = (a + byte as u32) % MOD_ADLER;
+    b = (b + a) % MOD_ADLER;
+  }
+  (b << 16) | a
+}
+
+// --- DEFLATE (RFC 1951) ---
+
+struct BitReader<'a> {
+  data: &'a [u8],
+  byte_pos: usize,
+  bit_pos: u8,
+}
+
+impl<'a> BitReader<'a> {
+  fn new(data: &'a [u8]) -> Self {
+    Self {
+      data,
+      byte_pos: 0,
+      bit_pos: 0,
+    }
+  }
+
+  fn read_bit(&mut self) -> Option<u8> {
+    let byte = *self.data.get(self.byte_pos)?;
+    let bit = (byte >> self.bit_pos) & 1;
+    self.bit_pos += 1;
+    if self.bit_pos == 8 {
+      self.bit_pos = 0;
+      self.byte_pos += 1;
+    }
+    Some(bit)
+  }
+
+  fn read_bits(&mut self, count: u8) -> Option<u32> {
+    let mut value = 0u32;
+    for i in 0..count {
+      value |= (self.read_bit()? as u32) << i;
+    }
+    Some(value)
+  }
+
+  fn align_to_byte(&mut self) {
+    if self.bit_pos != 0 {
+      self.bit_pos = 0;
+      self.byte_pos += 1;
+    }
+  }
+
+  fn read_aligned_byte(&mut self) -> Option<u8> {
+    let byte = *self.data.get(self.byte_pos)?;
+    self.byte_pos += 1;
+    Some(byte)
+  }
+}
+
+/// Canonical Huffman decode table: maps (code length, code value) to symbol.
+struct HuffmanTable {
+  codes: std::collections::HashMap<(u8, u16), u16>,
+  max_len: u8,
+}
+
+fn build_huffman(code_lengths: &[u8]) -> HuffmanTable {
+  let max_len = code_lengths.iter().copied().max().unwrap_or(0);
+  let mut bl_count = vec![0u32; max_len as usize + 1];
+  for &len in code_lengths {
+    if len > 0 {
+      bl_count[len as usize] += 1;
+    }
+  }
+
+  let mut code = 0u32;
+  let mut next_code = vec![0u32; max_len as usize + 1];
+  for bits in 1..=max_len as usize {
+    code = (code + bl_count[bits - 1]) << 1;
+    next_code[bits] = code;
+  }
+
+  let mut codes = std::collections::HashMap::new();
+  for (symbol, &len) in code_lengths.iter().enumerate() {
+    if len > 0 {
+      let assigned = next_code[len as usize];
+      next_code[len as usize] += 1;
+      codes.insert((len, assigned as u16), symbol as u16);
+    }
+  }
+
+  HuffmanTable { codes, max_len }
+}
+
+fn decode_symbol(reader: &mut BitReader, table: &HuffmanTable) -> Option<u16> {
+  let mut code: u16 = 0;
+  for len in 1..=table.max_len {
+    code = (code << 1) | reader.read_bit()? as u16;
+    if let Some(&symbol) = table.codes.get(&(len, code)) {
+      return Some(symbol);
+    }
+  }
+  None
+}
+
+fn fixed_tables() -> (HuffmanTable, HuffmanTable) {
+  let mut lit_lengths = [0u8; 288];
+  for (i, len) in lit_lengths.iter_mut().enumerate() {
+    *len = match i {
+      0..=143 => 8,
+      144..=255 => 9,
+      256..=279 => 7,
+      _ => 8,
+    };
+  }
+  let dist_lengths = [5u8; 30];
+  (build_huffman(&lit_lengths), build_huffman(&dist_lengths))
+}
+
+const CL_ORDER: [usize; 19] = [
+  16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+const LENGTH_BASE: [u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+  163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049,
+  3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+fn read_dynamic_tables(reader: &mut BitReader) -> Result<(HuffmanTable, HuffmanTable), String> {
+  let hlit = reader.read_bits(5).ok_or("truncated dynamic header")? as usize + 257;
+  let hdist = reader.read_bits(5).ok_or("truncated dynamic header")? as usize + 1;
+  let hclen = reader.read_bits(4).ok_or("truncated dynamic header")? as usize + 4;
+
+  let mut cl_lengths = [0u8; 19];
+  for &idx in CL_ORDER.iter().take(hclen) {
+    cl_lengths[idx] = reader.read_bits(3).ok_or("truncated code-length table")? as u8;
+  }
+  let cl_table = build_huffman(&cl_lengths);
+
+  let mut lengths = Vec::with_capacity(hlit + hdist);
+  while lengths.len() < hlit + hdist {
+    let symbol = decode_symbol(reader, &cl_table).ok_or("bad code-length symbol")?;
+    match symbol {
+      0..=15 => lengths.push(symbol as u8),
+      16 => {
+        let repeat = reader.read_bits(2).ok_or("truncated repeat")? + 3;
+        let prev = *lengths.last().ok_or("repeat with no previous length")?;
+        for _ in 0..repeat {
+          lengths.push(prev);
+        }
+      }
+      17 => {
+        let repeat = reader.read_bits(3).ok_or("truncated repeat")? + 3;
+        for _ in 0..repeat {
+          lengths.push(0);
+        }
+      }
+      18 => {
+        let repeat = reader.read_bits(7).ok_or("truncated repeat")? + 11;
+        for _ in 0..repeat {
+          lengths.push(0);
+        }
+      }
+      _ => return Err("invalid code-length symbol".into()),
+    }
+  }
+
+  let lit_lengths = &lengths[..hlit];
+  let dist_lengths = &lengths[hlit..hlit + hdist];
+  Ok((build_huffman(lit_lengths), build_huffman(dist_lengths)))
+}
+
+fn inflate_block(
+  reader: &mut BitReader,
+  lit_table: &HuffmanTable,
+  dist_table: &HuffmanTable,
+  out: &mut Vec<u8>,
+) -> Result<(), String> {
+  loop {
+    let symbol = decode_symbol(reader, lit_table).ok_or("bad literal/length code")?;
+    if symbol < 256 {
+      out.push(symbol as u8);
+    } else if symbol == 256 {
+      return Ok(());
+    } else {
+      let idx = (symbol - 257) as usize;
+      let base = *LENGTH_BASE.get(idx).ok_or("invalid length code")?;
+      let extra_bits = LENGTH_EXTRA[idx];
+      let extra = reader.read_bits(extra_bits).ok_or("truncated length extra bits")?;
+      let length = base as usize + extra as usize;
+
+      let dist_symbol = decode_symbol(reader, dist_table).ok_or("bad distance code")? as usize;
+      let dist_base = *DIST_BASE.get(dist_symbol).ok_or("invalid distance code")?;
+      let dist_extra_bits = DIST_EXTRA[dist_symbol];
+      let dist_extra = reader
+        .read_bits(dist_extra_bits)
+        .ok_or("truncated distance extra bits")?;
+      let distance = dist_base as usize + dist_extra as usize;
+
+      if distance == 0 || distance > out.len() {
+        return Err("back-reference distance out of range".into());
+      }
+      let start = out.len() - distance;
+      for i in 0..length {
+        out.push(out[start + i]);
+      }
+    }
+  }
+}
+
+/// Inflate a raw DEFLATE stream (no zlib header/trailer).
+pub fn inflate_raw(data: &[u8]) -> Result<Vec<u8>, String> {
+  let mut reader = BitReader::new(data);
+  let mut out = Vec::new();
+
+  loop {
+    let bfinal = reader.read_bit().ok_or("truncated block header")?;
+    let btype = reader.read_bits(2).ok_or("truncated block header")?;
+
+    match btype {
+      0 => {
+        reader.align_to_byte();
+        let len_lo = reader.read_aligned_byte().ok_or("truncated stored block")? as u16;
+        let len_hi = reader.read_aligned_byte().ok_or("truncated stored block")? as u16;
+        let len = len_lo | (len_hi << 8);
+        let _nlen_lo = reader.read_aligned_byte().ok_or("truncated stored block")?;
+        let _nlen_hi = reader.read_aligned_byte().ok_or("truncated stored block")?;
+        for _ in 0..len {
+          out.push(reader.read_aligned_byte().ok_or("truncated stored block")?);
+        }
+      }
+      1 => {
+        let (lit_table, dist_table) = fixed_tables();
+        inflate_block(&mut reader, &lit_table, &dist_table, &mut out)?;
+      }
+      2 => {
+        let (lit_table, dist_table) = read_dynamic_tables(&mut reader)?;
+        inflate_block(&mut reader, &lit_table, &dist_table, &mut out)?;
+      }
+      _ => return Err("invalid DEFLATE block type".into()),
+    }
+
+    if bfinal == 1 {
+      return Ok(out);
+    }
+  }
+}
+
+/// Re-deflate as a sequence of uncompressed "stored" blocks. Valid DEFLATE,
+/// just not space-efficient — good enough for a one-off icon conversion.
+fn deflate_stored(data: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 5);
+  let mut offset = 0;
+
+  loop {
+    let remaining = data.len() - offset;
+    let chunk_len = remaining.min(65535);
+    let is_final = offset + chunk_len >= data.len();
+
+    out.push(if is_final { 0x01 } else { 0x00 });
+    let len = chunk_len as u16;
+    out.extend_from_slice(&len.to_le_bytes());
+    out.extend_from_slice(&(!len).to_le_bytes());
+    out.extend_from_slice(&data[offset..offset + chunk_len]);
+
+    offset += chunk_len;
+    if is_final {
+      return out;
+    }
+  }
+}
+
+fn zlib_wrap(raw_deflate: &[u8], uncompressed: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(raw_deflate.len() + 6);
+  out.push(0x78); // CMF: deflate, 32K window
+  out.push(0x01); // FLG: FCHECK makes (0x78 << 8 | FLG) a multiple of 31
+  out.extend_from_slice(raw_deflate);
+  out.extend_from_slice(&adler32(uncompressed).to_be_bytes());
+  out
+}
+
+// --- PNG chunk framing ---
+
+struct PngChunk {
+  chunk_type: [u8; 4],
+  data: Vec<u8>,
+}
+
+fn parse_chunks(mut rest: &[u8]) -> Option<Vec<PngChunk>> {
+  let mut chunks = Vec::new();
+  while rest.len() >= 12 {
+    let len = u32::from_be_bytes(rest[0..4].try_into().ok()?) as usize;
+    let chunk_type: [u8; 4] = rest[4..8].try_into().ok()?;
+    if rest.len() < 8 + len + 4 {
+      return None;
+    }
+    let data = rest[8..8 + len].to_vec();
+    let is_end = &chunk_type == b"IEND";
+    chunks.push(PngChunk { chunk_type, data });
+    rest = &rest[8 + len + 4..];
+    if is_end {
+      break;
+    }
+  }
+  Some(chunks)
+}
+
+fn write_chunk(out: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+  out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+  let mut crc_input = Vec::with_capacity(4 + data.len());
+  crc_input.extend_from_slice(chunk_type);
+  crc_input.extend_from_slice(data);
+  out.extend_from_slice(chunk_type);
+  out.extend_from_slice(data);
+  out.extend_from_slice(&crc32(&crc_input).to_be_bytes());
+}
+
+// --- Scanline filtering (PNG spec section 9) ---
+
+fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+  let p = a as i32 + b as i32 - c as i32;
+  let pa = (p - a as i32).abs();
+  let pb = (p - b as i32).abs();
+  let pc = (p - c as i32).abs();
+  if pa <= pb && pa <= pc {
+    a
+  } else if pb <= pc {
+    b
+  } else {
+    c
+  }
+}
+
+fn unfilter(raw: &[u8], width: usize, height: usize, bpp: usize) -> Option<Vec<u8>> {
+  let stride = width * bpp;
+  let mut out = vec![0u8; height * stride];
+  let mut prev = vec![0u8; stride];
+  let mut pos = 0;
+
+  for row in 0..height {
+    let filter_type = *raw.get(pos)?;
+    pos += 1;
+    let filtered = raw.get(pos..pos + stride)?;
+    pos += stride;
+
+    let mut current = vec![0u8; stride];
+    for i in 0..stride {
+      let a = if i >= bpp { current[i - bpp] } else { 0 };
+      let b = prev[i];
+      let c = if i >= bpp { prev[i - bpp] } else { 0 };
+      let x = filtered[i];
+      current[i] = match filter_type {
+        0 => x,
+        1 => x.wrapping_add(a),
+        2 => x.wrapping_add(b),
+        3 => x.wrapping_add(((a as u16 + b as u16) / 2) as u8),
+        4 => x.wrapping_add(paeth_predictor(a, b, c)),
+        _ => return None,
+      };
+    }
+
+    out[row * stride..(row + 1) * stride].copy_from_slice(&current);
+    prev = current;
+  }
+
+  Some(out)
+}
+
+/// Prepend filter type 0 (None) to every scanline. Simplest valid choice;
+/// leaves compression quality to `deflate_stored`, which doesn't try either.
+fn refilter_none(pixels: &[u8], width: usize, height: usize, bpp: usize) -> Vec<u8> {
+  let stride = width * bpp;
+  let mut out = Vec::with_capacity(height * (stride + 1));
+  for row in 0..height {
+    out.push(0);
+    out.extend_from_slice(&pixels[row * stride..(row + 1) * stride]);
+  }
+  out
+}
+
+fn unpremultiply_channel(channel: u8, alpha: u8) -> u8 {
+  if alpha == 0 {
+    return 0;
+  }
+  ((channel as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8
+}
+
+/// Swap CgBI's premultiplied BGRA pixels back to straight-alpha RGBA.
+fn convert_bgra_premultiplied_to_rgba(pixels: &mut [u8]) {
+  for px in pixels.chunks_exact_mut(4) {
+    let (b, g, r, a) = (px[0], px[1], px[2], px[3]);
+    px[0] = unpremultiply_channel(r, a);
+    px[1] = unpremultiply_channel(g, a);
+    px[2] = unpremultiply_channel(b, a);
+    px[3] = a;
+  }
+}
+
+// --- CgBI entry point ---
+
+/// Convert an Apple "CgBI" PNG (pngcrush-optimized, premultiplied BGRA, raw
+/// unwrapped DEFLATE) into a standard PNG decodable by any viewer. Returns
+/// the input unchanged if it's already a standard PNG with no `CgBI` chunk.
+/// Only 8-bit RGBA (color type 6) is supported, which covers every iOS app
+/// icon observed in the wild; anything else returns `None` so the caller can
+/// fall back to a placeholder.
+pub fn deoptimize_cgbi_png(data: &[u8]) -> Option<Vec<u8>> {
+  if !data.starts_with(&PNG_SIGNATURE) {
+    return None;
+  }
+
+  let chunks = parse_chunks(&data[8..])?;
+  let has_cgbi = chunks
+    .first()
+    .map(|c| &c.chunk_type == b"CgBI")
+    .unwrap_or(false);
+  if !has_cgbi {
+    return Some(data.to_vec());
+  }
+
+  let ihdr = &chunks.iter().find(|c| &c.chunk_type == b"IHDR")?.data;
+  if ihdr.len() < 13 {
+    return None;
+  }
+  let width = u32::from_be_bytes(ihdr[0..4].try_into().ok()?) as usize;
+  let height = u32::from_be_bytes(ihdr[4..8].try_into().ok()?) as usize;
+  let bit_depth = ihdr[8];
+  let color_type = ihdr[9];
+  if bit_depth != 8 || color_type != 6 {
+    return None;
+  }
+  let bpp = 4;
+
+  let idat: Vec<u8> = chunks
+    .iter()
+    .filter(|c| &c.chunk_type == b"IDAT")
+    .flat_map(|c| c.data.iter().copied())
+    .collect();
+
+  let filtered = inflate_raw(&idat).ok()?;
+  let mut pixels = unfilter(&filtered, width, height, bpp)?;
+  convert_bgra_premultiplied_to_rgba(&mut pixels);
+
+  let refiltered = refilter_none(&pixels, width, height, bpp);
+  let compressed = zlib_wrap(&deflate_stored(&refiltered), &refiltered);
+
+  let mut new_ihdr = [0u8; 13];
+  new_ihdr.copy_from_slice(&ihdr[..13]);
+  new_ihdr[10] = 0; // compression method
+  new_ihdr[11] = 0; // filter method
+  new_ihdr[12] = 0; // interlace method
+
+  let mut out = Vec::with_capacity(8 + 25 + compressed.len() + 20 + 12);
+  out.extend_from_slice(&PNG_SIGNATURE);
+  write_chunk(&mut out, b"IHDR", &new_ihdr);
+  write_chunk(&mut out, b"IDAT", &compressed);
+  write_chunk(&mut out, b"IEND", &[]);
+  Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn build_standard_png(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+    let bpp = 4;
+    let stride = width as usize * bpp;
+    let mut filtered = Vec::with_capacity(height as usize * (stride + 1));
+    for row in 0..height as usize {
+      filtered.push(0);
+      filtered.extend_from_slice(&pixels[row * stride..(row + 1) * stride]);
+    }
+    let compressed = zlib_wrap(&deflate_stored(&filtered), &filtered);
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&PNG_SIGNATURE);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &compressed);
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+  }
+
+  fn build_cgbi_png(width: u32, height: u32, bgra_premultiplied: &[u8]) -> Vec<u8> {
+    let bpp = 4;
+    let stride = width as usize * bpp;
+    let mut filtered = Vec::with_capacity(height as usize * (stride + 1));
+    for row in 0..height as usize {
+      filtered.push(0);
+      filtered.extend_from_slice(&bgra_premultiplied[row * stride..(row + 1) * stride]);
+    }
+    // CgBI stores the IDAT payload as raw DEFLATE with no zlib wrapper.
+    let raw_deflate = deflate_stored(&filtered);
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&PNG_SIGNATURE);
+    write_chunk(&mut out, b"CgBI", &[0x00, 0x00, 0x20, 0x02]);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &raw_deflate);
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+  }
+
+  #[test]
+  fn test_crc32_known_vector() {
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+  }
+
+  #[test]
+  fn test_adler32_known_vector() {
+    assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+  }
+
+  #[test]
+  fn test_deflate_stored_roundtrip() {
+    let data = b"the quick brown fox jumps over the lazy dog".to_vec();
+    let compressed = deflate_stored(&data);
+    let inflated = inflate_raw(&compressed).unwrap();
+    assert_eq!(inflated, data);
+  }
+
+  #[test]
+  fn test_deoptimize_cgbi_png_single_opaque_pixel() {
+    // One fully-opaque red pixel, stored as premultiplied BGRA: B=0 G=0 R=255 A=255.
+    let cgbi = build_cgbi_png(1, 1, &[0, 0, 255, 255]);
+    let standard = deoptimize_cgbi_png(&cgbi).expect("conversion should succeed");
+
+    assert!(standard.starts_with(&PNG_SIGNATURE));
+    let chunks = parse_chunks(&standard[8..]).unwrap();
+    assert!(!chunks.iter().any(|c| &c.chunk_type == b"CgBI"));
+
+    let idat: Vec<u8> = chunks
+      .iter()
+      .filter(|c| &c.chunk_type == b"IDAT")
+      .flat_map(|c| c.data.iter().copied())
+      .collect();
+    // Strip the 2-byte zlib header and 4-byte Adler-32 trailer before inflating.
+    let raw = inflate_raw(&idat[2..idat.len() - 4]).unwrap();
+    let pixels = unfilter(&raw, 1, 1, 4).unwrap();
+    assert_eq!(pixels, vec![255, 0, 0, 255]); // straight-alpha RGBA
+  }
+
+  #[test]
+  fn test_deoptimize_cgbi_png_unpremultiplies_translucent_pixel() {
+    // 50%-alpha red, premultiplied: R*a/255 = 255*128/255 = 128, stored as BGRA.
+    let cgbi = build_cgbi_png(1, 1, &[0, 0, 128, 128]);
+    let standard = deoptimize_cgbi_png(&cgbi).unwrap();
+    let chunks = parse_chunks(&standard[8..]).unwrap();
+    let idat: Vec<u8> = chunks
+      .iter()
+      .filter(|c| &c.chunk_type == b"IDAT")
+      .flat_map(|c| c.data.iter().copied())
+      .collect();
+    let raw = inflate_raw(&idat[2..idat.len() - 4]).unwrap();
+    let pixels = unfilter(&raw, 1, 1, 4).unwrap();
+    assert_eq!(pixels[0], 255); // un-premultiplied red channel
+    assert_eq!(pixels[3], 128); // alpha carried through unchanged
+  }
+
+  #[test]
+  fn test_deoptimize_cgbi_png_passes_through_standard_png() {
+    let standard = build_standard_png(1, 1, &[10, 20, 30, 255]);
+    assert_eq!(deoptimize_cgbi_png(&standard).unwrap(), standard);
+  }
+
+  #[test]
+  fn test_deoptimize_cgbi_png_rejects_non_png() {
+    assert_eq!(deoptimize_cgbi_png(b"not a png"), None);
+  }
+}
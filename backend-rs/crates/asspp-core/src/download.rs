@@ -1,5 +1,25 @@
 use crate::security::{sanitize_path_segment, validate_download_url, validate_path_segment};
-use crate::types::{CreateDownloadRequest, DownloadTask, TaskStatus};
+use crate::types::{CreateDownloadByIdentifierRequest, CreateDownloadRequest, DownloadTask, Software, TaskStatus};
+
+/// Fold a resolved [`Software`] into a [`CreateDownloadByIdentifierRequest`],
+/// producing the full [`CreateDownloadRequest`] a caller who already knows
+/// `software` would have hand-assembled. The caller is expected to have
+/// resolved `software` itself (e.g. via `metadata_client::identifier_from_parts`
+/// plus a platform HTTP client) before calling this.
+pub fn resolve_create_request(
+  req: CreateDownloadByIdentifierRequest,
+  software: Software,
+) -> CreateDownloadRequest {
+  CreateDownloadRequest {
+    software,
+    account_hash: req.account_hash,
+    download_url: req.download_url,
+    sinfs: req.sinfs,
+    itunes_metadata: req.itunes_metadata,
+    expected_sha256: req.expected_sha256,
+    plist_overrides_xml: req.plist_overrides_xml,
+  }
+}
 
 /// Validate a create-download request body.
 pub fn validate_create_request(req: &CreateDownloadRequest) -> Result<(), String> {
@@ -12,6 +32,12 @@ pub fn validate_create_request(req: &CreateDownloadRequest) -> Result<(), String
   validate_path_segment(&req.software.bundle_id, "bundleID")?;
   validate_path_segment(&req.software.version, "version")?;
 
+  if let Some(hash) = &req.expected_sha256 {
+    if !is_valid_sha256_hex(hash) {
+      return Err("expectedSha256 must be a 64-character lowercase hex string".into());
+    }
+  }
+
   Ok(())
 }
 
@@ -50,23 +76,37 @@ pub fn new_task(req: CreateDownloadRequest) -> DownloadTask {
     speed: "0 B/s".into(),
     error: None,
     file_path: None,
+    downloaded_bytes: 0,
+    resume_etag: None,
+    staging_upload: None,
+    integrity: None,
+    retry_count: 0,
+    expected_sha256: req.expected_sha256,
+    md5_size: None,
+    md5s: None,
+    plist_overrides_xml: req.plist_overrides_xml,
     created_at: chrono_now_iso(),
   }
 }
 
 fn chrono_now_iso() -> String {
+  format_unix_timestamp(unix_now_secs())
+}
+
+/// Current time as Unix seconds, read the wasm32-friendly way (no blocking
+/// syscalls inside a Worker).
+pub fn unix_now_secs() -> u64 {
   #[cfg(not(target_arch = "wasm32"))]
   {
     let now = std::time::SystemTime::now();
     let dur = now
       .duration_since(std::time::UNIX_EPOCH)
       .unwrap_or_default();
-    format_unix_timestamp(dur.as_secs())
+    dur.as_secs()
   }
   #[cfg(target_arch = "wasm32")]
   {
-    let ms = js_sys::Date::now();
-    format_unix_timestamp((ms / 1000.0) as u64)
+    (js_sys::Date::now() / 1000.0) as u64
   }
 }
 
@@ -126,12 +166,272 @@ fn is_leap(year: u64) -> bool {
   (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
 }
 
+// --- Resume support ---
+
+/// Build the `Range` header value to resume a transfer after `downloaded_bytes`.
+pub fn range_header(downloaded_bytes: u64) -> String {
+  format!("bytes={}-", downloaded_bytes)
+}
+
+/// What to do with a partially-downloaded file once the server has responded
+/// to a resume attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResumeDecision {
+  /// Server honored the range; keep appending to the partial file.
+  Resume,
+  /// Server ignored the range (or the asset changed); discard the partial
+  /// file and download from scratch.
+  RestartFromZero,
+}
+
+/// Decide how to proceed with a resume attempt given the response status and
+/// (if present) the `Content-Range` header.
+///
+/// A `206` is only trusted if its total length matches `expected_total`;
+/// a `200` means the server ignored the range entirely. Anything else is an
+/// error the caller should surface instead of guessing.
+pub fn evaluate_resume_response(
+  status: u16,
+  content_range: Option<&str>,
+  expected_total: u64,
+) -> Result<ResumeDecision, String> {
+  match status {
+    206 => {
+      let total = content_range
+        .and_then(parse_content_range_total)
+        .ok_or("206 response missing a valid Content-Range header")?;
+      if total != expected_total {
+        Ok(ResumeDecision::RestartFromZero)
+      } else {
+        Ok(ResumeDecision::Resume)
+      }
+    }
+    200 => Ok(ResumeDecision::RestartFromZero),
+    other => Err(format!("Unexpected status {other} while resuming download")),
+  }
+}
+
+/// Parse the total length out of a `Content-Range: bytes 0-99/1000` value.
+fn parse_content_range_total(value: &str) -> Option<u64> {
+  let total_str = value.rsplit('/').next()?;
+  if total_str == "*" {
+    return None;
+  }
+  total_str.parse().ok()
+}
+
+/// Check whether a resume is still safe to perform given the identity
+/// (`ETag`/`Last-Modified`) captured on the first response.
+///
+/// Returns `true` when there is nothing to compare against yet (first
+/// attempt) or when the identities match; `false` means the remote asset
+/// changed and the partial file must be discarded.
+pub fn resume_identity_matches(stored: Option<&str>, fetched: Option<&str>) -> bool {
+  match stored {
+    None => true,
+    Some(stored) => fetched == Some(stored),
+  }
+}
+
+/// Compute throughput over only the newly received bytes since the last
+/// sample, so resuming a transfer doesn't report an inflated instantaneous
+/// speed for bytes that were already on disk.
+pub fn bytes_per_sec(newly_received: u64, elapsed_secs: f64) -> f64 {
+  if elapsed_secs <= 0.0 {
+    return 0.0;
+  }
+  newly_received as f64 / elapsed_secs
+}
+
+// --- Range request serving ---
+
+/// A single byte range requested by a client, inclusive on both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+  pub start: u64,
+  pub end: u64,
+}
+
+impl ByteRange {
+  pub fn len(&self) -> u64 {
+    self.end - self.start + 1
+  }
+
+  /// The `Content-Range` header value for a `206` response to this range.
+  pub fn content_range_header(&self, total_size: u64) -> String {
+    format!("bytes {}-{}/{}", self.start, self.end, total_size)
+  }
+}
+
+/// Outcome of checking a client's `Range` header against a known total size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeOutcome {
+  /// No (usable) range was requested; serve the whole resource.
+  FullResponse,
+  /// A valid, in-bounds single byte range.
+  Partial(ByteRange),
+  /// A syntactically valid byte range that is out of bounds for this
+  /// resource; the caller should answer `416 Range Not Satisfiable`.
+  Unsatisfiable,
+}
+
+/// Parse a `Range: bytes=...` header against a known total size. Only a
+/// single range is supported (the common case for a resumed IPA download);
+/// a comma-separated multi-range request falls back to `FullResponse`.
+pub fn parse_range_header(value: Option<&str>, total_size: u64) -> RangeOutcome {
+  let Some(spec) = value.and_then(|v| v.strip_prefix("bytes=")) else {
+    return RangeOutcome::FullResponse;
+  };
+  if spec.contains(',') {
+    return RangeOutcome::FullResponse;
+  }
+  let Some((start_str, end_str)) = spec.split_once('-') else {
+    return RangeOutcome::FullResponse;
+  };
+
+  if total_size == 0 {
+    return RangeOutcome::Unsatisfiable;
+  }
+
+  let range = if start_str.is_empty() {
+    // Suffix range: the last `end_str` bytes.
+    let Ok(suffix_len) = end_str.parse::<u64>() else {
+      return RangeOutcome::FullResponse;
+    };
+    if suffix_len == 0 {
+      return RangeOutcome::Unsatisfiable;
+    }
+    ByteRange {
+      start: total_size.saturating_sub(suffix_len),
+      end: total_size - 1,
+    }
+  } else {
+    let Ok(start) = start_str.parse::<u64>() else {
+      return RangeOutcome::FullResponse;
+    };
+    let end = if end_str.is_empty() {
+      total_size - 1
+    } else {
+      match end_str.parse::<u64>() {
+        Ok(v) => v.min(total_size - 1),
+        Err(_) => return RangeOutcome::FullResponse,
+      }
+    };
+    ByteRange { start, end }
+  };
+
+  if range.start >= total_size || range.start > range.end {
+    RangeOutcome::Unsatisfiable
+  } else {
+    RangeOutcome::Partial(range)
+  }
+}
+
+/// Whether a `Range` header should still be honored given `If-Range`, per
+/// RFC 7233 §3.2: if the client's `If-Range` value doesn't match the
+/// representation's current `ETag`, the range must be ignored and the whole
+/// resource served instead (the client's cached bytes are stale).
+pub fn if_range_satisfied(if_range: Option<&str>, etag: &str) -> bool {
+  match if_range {
+    None => true,
+    Some(value) => value == etag,
+  }
+}
+
+// --- Retry support ---
+
+/// How the delay between retry attempts grows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryBackoff {
+  /// Always wait `delay_secs`.
+  Fixed,
+  /// Wait `delay_secs * 2^(attempt - 1)`.
+  Exponential,
+}
+
+/// Retry policy for a flaky upstream fetch (modeled on nextest's retry
+/// config). `count` is the number of retries *after* the initial attempt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+  pub count: u32,
+  pub backoff: RetryBackoff,
+  pub delay_secs: f64,
+  pub jitter: bool,
+  pub max_delay_secs: f64,
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      count: 3,
+      backoff: RetryBackoff::Exponential,
+      delay_secs: 1.0,
+      jitter: true,
+      max_delay_secs: 30.0,
+    }
+  }
+}
+
+/// Whether an HTTP status from the Apple CDN is worth retrying. Transient
+/// 408/429/5xx are; anything else (403/404/...) won't change on retry.
+pub fn is_retryable_status(status: u16) -> bool {
+  matches!(status, 408 | 429 | 500 | 502 | 503 | 504)
+}
+
+/// Whether another attempt should be made, given how many retries have
+/// already happened.
+pub fn should_retry(retries_so_far: u32, policy: &RetryPolicy) -> bool {
+  retries_so_far < policy.count
+}
+
+/// Delay before the `attempt`th retry (1 = first retry), in seconds.
+///
+/// `jitter_fraction` must be in `[0, 1)`; the caller supplies it (e.g. from
+/// `Math.random()` on Workers) so this stays a pure, testable function. The
+/// final delay — base plus jitter — is capped at `max_delay_secs`.
+pub fn delay_for_attempt(policy: &RetryPolicy, attempt: u32, jitter_fraction: f64) -> f64 {
+  let base = match policy.backoff {
+    RetryBackoff::Fixed => policy.delay_secs,
+    RetryBackoff::Exponential => policy.delay_secs * 2f64.powi(attempt.saturating_sub(1) as i32),
+  };
+  let with_jitter = if policy.jitter {
+    base + policy.delay_secs * jitter_fraction.clamp(0.0, 1.0)
+  } else {
+    base
+  };
+  with_jitter.min(policy.max_delay_secs)
+}
+
 /// Validate accountHash format (hex string, >= 8 chars).
 pub fn validate_account_hash(hash: &str) -> bool {
   hash.len() >= 8
     && hash.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
 }
 
+// --- Content addressing ---
+
+/// Whether a string looks like a lowercase SHA-256 hex digest.
+fn is_valid_sha256_hex(value: &str) -> bool {
+  value.len() == 64 && value.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// R2 key for a content-addressed blob.
+pub fn blob_key(sha256_hex: &str) -> String {
+  format!("blobs/{sha256_hex}")
+}
+
+/// Check a freshly computed hash against a caller-supplied expectation, if
+/// any. Lets a caller who already knows the expected hash reject a
+/// tampered/truncated CDN response before it's ever stored.
+pub fn verify_expected_hash(expected: Option<&str>, actual_sha256_hex: &str) -> Result<(), String> {
+  match expected {
+    Some(exp) if exp != actual_sha256_hex => Err(format!(
+      "downloaded content hash {actual_sha256_hex} does not match expected {exp}"
+    )),
+    _ => Ok(()),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -171,6 +471,8 @@ mod tests {
         sinf: "dGVzdA==".into(),
       }],
       itunes_metadata: None,
+      expected_sha256: None,
+      plist_overrides_xml: None,
     };
     assert!(validate_create_request(&req).is_ok());
   }
@@ -183,6 +485,8 @@ mod tests {
       download_url: "https://cdn.apple.com/file.ipa".into(),
       sinfs: vec![],
       itunes_metadata: None,
+      expected_sha256: None,
+      plist_overrides_xml: None,
     };
     assert!(validate_create_request(&req).is_err());
   }
@@ -198,6 +502,8 @@ mod tests {
         sinf: "dGVzdA==".into(),
       }],
       itunes_metadata: None,
+      expected_sha256: None,
+      plist_overrides_xml: None,
     };
     assert!(validate_create_request(&req).is_err());
   }
@@ -241,6 +547,8 @@ mod tests {
         sinf: "dGVzdA==".into(),
       }],
       itunes_metadata: Some("bWV0YQ==".into()),
+      expected_sha256: None,
+      plist_overrides_xml: None,
     };
     let task = new_task(req);
     assert!(!task.id.is_empty());
@@ -248,5 +556,248 @@ mod tests {
     assert_eq!(task.progress, 0);
     assert_eq!(task.speed, "0 B/s");
     assert!(task.itunes_metadata.is_some());
+    assert_eq!(task.downloaded_bytes, 0);
+    assert!(task.resume_etag.is_none());
+    assert_eq!(task.retry_count, 0);
+  }
+
+  #[test]
+  fn test_range_header() {
+    assert_eq!(range_header(0), "bytes=0-");
+    assert_eq!(range_header(1024), "bytes=1024-");
+  }
+
+  #[test]
+  fn test_evaluate_resume_response_partial_content() {
+    let decision = evaluate_resume_response(206, Some("bytes 100-999/1000"), 1000).unwrap();
+    assert_eq!(decision, ResumeDecision::Resume);
+  }
+
+  #[test]
+  fn test_evaluate_resume_response_size_changed() {
+    let decision = evaluate_resume_response(206, Some("bytes 100-999/2000"), 1000).unwrap();
+    assert_eq!(decision, ResumeDecision::RestartFromZero);
+  }
+
+  #[test]
+  fn test_evaluate_resume_response_ignored_range() {
+    let decision = evaluate_resume_response(200, None, 1000).unwrap();
+    assert_eq!(decision, ResumeDecision::RestartFromZero);
+  }
+
+  #[test]
+  fn test_evaluate_resume_response_missing_content_range() {
+    assert!(evaluate_resume_response(206, None, 1000).is_err());
+  }
+
+  #[test]
+  fn test_evaluate_resume_response_unexpected_status() {
+    assert!(evaluate_resume_response(500, None, 1000).is_err());
+  }
+
+  #[test]
+  fn test_resume_identity_matches() {
+    assert!(resume_identity_matches(None, Some("\"abc\"")));
+    assert!(resume_identity_matches(Some("\"abc\""), Some("\"abc\"")));
+    assert!(!resume_identity_matches(Some("\"abc\""), Some("\"def\"")));
+    assert!(!resume_identity_matches(Some("\"abc\""), None));
+  }
+
+  #[test]
+  fn test_bytes_per_sec() {
+    assert_eq!(bytes_per_sec(1024, 2.0), 512.0);
+    assert_eq!(bytes_per_sec(1024, 0.0), 0.0);
+  }
+
+  #[test]
+  fn test_is_retryable_status() {
+    for status in [408, 429, 500, 502, 503, 504] {
+      assert!(is_retryable_status(status), "{status} should be retryable");
+    }
+    for status in [400, 401, 403, 404, 410] {
+      assert!(!is_retryable_status(status), "{status} should not be retryable");
+    }
+  }
+
+  #[test]
+  fn test_should_retry() {
+    let policy = RetryPolicy {
+      count: 2,
+      ..RetryPolicy::default()
+    };
+    assert!(should_retry(0, &policy));
+    assert!(should_retry(1, &policy));
+    assert!(!should_retry(2, &policy));
+  }
+
+  #[test]
+  fn test_delay_for_attempt_exponential_no_jitter() {
+    let policy = RetryPolicy {
+      backoff: RetryBackoff::Exponential,
+      delay_secs: 1.0,
+      jitter: false,
+      max_delay_secs: 100.0,
+      ..RetryPolicy::default()
+    };
+    assert_eq!(delay_for_attempt(&policy, 1, 0.0), 1.0);
+    assert_eq!(delay_for_attempt(&policy, 2, 0.0), 2.0);
+    assert_eq!(delay_for_attempt(&policy, 3, 0.0), 4.0);
+  }
+
+  #[test]
+  fn test_delay_for_attempt_fixed() {
+    let policy = RetryPolicy {
+      backoff: RetryBackoff::Fixed,
+      delay_secs: 2.0,
+      jitter: false,
+      max_delay_secs: 100.0,
+      ..RetryPolicy::default()
+    };
+    assert_eq!(delay_for_attempt(&policy, 1, 0.0), 2.0);
+    assert_eq!(delay_for_attempt(&policy, 5, 0.99), 2.0);
+  }
+
+  #[test]
+  fn test_delay_for_attempt_jitter_within_bounds() {
+    let policy = RetryPolicy {
+      backoff: RetryBackoff::Fixed,
+      delay_secs: 2.0,
+      jitter: true,
+      max_delay_secs: 100.0,
+      ..RetryPolicy::default()
+    };
+    assert_eq!(delay_for_attempt(&policy, 1, 0.0), 2.0);
+    assert_eq!(delay_for_attempt(&policy, 1, 0.5), 3.0);
+  }
+
+  #[test]
+  fn test_delay_for_attempt_capped_by_max_delay() {
+    let policy = RetryPolicy {
+      backoff: RetryBackoff::Exponential,
+      delay_secs: 10.0,
+      jitter: true,
+      max_delay_secs: 15.0,
+      ..RetryPolicy::default()
+    };
+    assert_eq!(delay_for_attempt(&policy, 4, 0.99), 15.0);
+  }
+
+  #[test]
+  fn test_blob_key() {
+    assert_eq!(blob_key("abc123"), "blobs/abc123");
+  }
+
+  #[test]
+  fn test_verify_expected_hash_matches() {
+    assert!(verify_expected_hash(Some("abc"), "abc").is_ok());
+    assert!(verify_expected_hash(None, "abc").is_ok());
+  }
+
+  #[test]
+  fn test_verify_expected_hash_mismatch() {
+    assert!(verify_expected_hash(Some("abc"), "def").is_err());
+  }
+
+  #[test]
+  fn test_parse_range_header_missing_is_full_response() {
+    assert_eq!(parse_range_header(None, 1000), RangeOutcome::FullResponse);
+  }
+
+  #[test]
+  fn test_parse_range_header_simple_range() {
+    assert_eq!(
+      parse_range_header(Some("bytes=0-499"), 1000),
+      RangeOutcome::Partial(ByteRange { start: 0, end: 499 })
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_open_ended() {
+    assert_eq!(
+      parse_range_header(Some("bytes=500-"), 1000),
+      RangeOutcome::Partial(ByteRange { start: 500, end: 999 })
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_suffix() {
+    assert_eq!(
+      parse_range_header(Some("bytes=-100"), 1000),
+      RangeOutcome::Partial(ByteRange { start: 900, end: 999 })
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_end_clamped_to_total() {
+    assert_eq!(
+      parse_range_header(Some("bytes=0-999999"), 1000),
+      RangeOutcome::Partial(ByteRange { start: 0, end: 999 })
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_start_past_end_is_unsatisfiable() {
+    assert_eq!(
+      parse_range_header(Some("bytes=1000-1999"), 1000),
+      RangeOutcome::Unsatisfiable
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_zero_total_size_is_unsatisfiable() {
+    assert_eq!(
+      parse_range_header(Some("bytes=0-10"), 0),
+      RangeOutcome::Unsatisfiable
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_multi_range_falls_back_to_full() {
+    assert_eq!(
+      parse_range_header(Some("bytes=0-10,20-30"), 1000),
+      RangeOutcome::FullResponse
+    );
+  }
+
+  #[test]
+  fn test_parse_range_header_malformed_falls_back_to_full() {
+    assert_eq!(
+      parse_range_header(Some("bytes=abc-def"), 1000),
+      RangeOutcome::FullResponse
+    );
+  }
+
+  #[test]
+  fn test_byte_range_len_and_content_range_header() {
+    let range = ByteRange { start: 0, end: 499 };
+    assert_eq!(range.len(), 500);
+    assert_eq!(range.content_range_header(1000), "bytes 0-499/1000");
+  }
+
+  #[test]
+  fn test_if_range_satisfied() {
+    assert!(if_range_satisfied(None, "\"abc\""));
+    assert!(if_range_satisfied(Some("\"abc\""), "\"abc\""));
+    assert!(!if_range_satisfied(Some("\"abc\""), "\"def\""));
+  }
+
+  #[test]
+  fn test_validate_create_request_rejects_malformed_expected_hash() {
+    let mut req = CreateDownloadRequest {
+      software: sample_software(),
+      account_hash: "abcdef1234567890".into(),
+      download_url: "https://cdn.apple.com/file.ipa".into(),
+      sinfs: vec![Sinf {
+        id: 0,
+        sinf: "dGVzdA==".into(),
+      }],
+      itunes_metadata: None,
+      expected_sha256: Some("not-a-hash".into()),
+      plist_overrides_xml: None,
+    };
+    assert!(validate_create_request(&req).is_err());
+
+    req.expected_sha256 = Some("a".repeat(64));
+    assert!(validate_create_request(&req).is_ok());
   }
 }
@@ -0,0 +1,360 @@
+//! Post-injection integrity manifests.
+//!
+//! Once an IPA has been downloaded and SINF/`iTunesMetadata.plist` entries
+//! injected, we record a SHA-256 of the final bytes alongside the list of
+//! paths that were injected, so a later read can tell bit rot (hash
+//! mismatch, size unchanged) apart from a process killed mid-write (size
+//! mismatch) without re-downloading anything.
+
+use crate::types::IntegrityManifest;
+
+/// Build the manifest for a completed package.
+pub fn build_manifest(data: &[u8], injected_files: &[String]) -> IntegrityManifest {
+  IntegrityManifest {
+    sha256: sha256_hex(data),
+    size: data.len() as u64,
+    injected_files: injected_files.to_vec(),
+  }
+}
+
+/// Why a package failed to verify against its manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntegrityError {
+  SizeMismatch { expected: u64, actual: u64 },
+  HashMismatch { expected: String, actual: String },
+}
+
+impl std::fmt::Display for IntegrityError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::SizeMismatch { expected, actual } => {
+        write!(f, "size mismatch: expected {expected} bytes, got {actual}")
+      }
+      Self::HashMismatch { expected, actual } => {
+        write!(f, "sha256 mismatch: expected {expected}, got {actual}")
+      }
+    }
+  }
+}
+
+/// Recompute the hash of `data` and compare it against `manifest`.
+pub fn verify(data: &[u8], manifest: &IntegrityManifest) -> Result<(), IntegrityError> {
+  let actual_size = data.len() as u64;
+  if actual_size != manifest.size {
+    return Err(IntegrityError::SizeMismatch {
+      expected: manifest.size,
+      actual: actual_size,
+    });
+  }
+
+  let actual_hash = sha256_hex(data);
+  if actual_hash != manifest.sha256 {
+    return Err(IntegrityError::HashMismatch {
+      expected: manifest.sha256.clone(),
+      actual: actual_hash,
+    });
+  }
+
+  Ok(())
+}
+
+/// SHA-256 of `data`, lowercase hex encoded.
+pub fn sha256_hex(data: &[u8]) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(data);
+  hex_encode(hasher.finalize())
+}
+
+/// Lowercase-hex encode a digest. Exposed so callers hashing incrementally
+/// with [`Sha256`] directly (e.g. R2 multipart uploads) can format the final
+/// digest the same way [`sha256_hex`] does.
+pub fn hex_encode(bytes: [u8; 32]) -> String {
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Incremental SHA-256, for hashing a stream without holding it all in
+/// memory at once (e.g. R2 multipart upload parts as they arrive).
+#[derive(Clone)]
+pub struct Sha256 {
+  h: [u32; 8],
+  buffer: Vec<u8>,
+  total_len: u64,
+}
+
+impl Default for Sha256 {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Sha256 {
+  pub fn new() -> Self {
+    Self {
+      h: [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+      ],
+      buffer: Vec::with_capacity(64),
+      total_len: 0,
+    }
+  }
+
+  /// Feed more bytes into the hash. Can be called any number of times.
+  pub fn update(&mut self, data: &[u8]) {
+    self.total_len += data.len() as u64;
+    self.buffer.extend_from_slice(data);
+
+    let mut offset = 0;
+    while self.buffer.len() - offset >= 64 {
+      let block: [u8; 64] = self.buffer[offset..offset + 64].try_into().unwrap();
+      process_block(&mut self.h, &block);
+      offset += 64;
+    }
+    self.buffer.drain(..offset);
+  }
+
+  /// Finish the hash and return the 32-byte digest.
+  pub fn finalize(mut self) -> [u8; 32] {
+    let bit_len = self.total_len * 8;
+    self.buffer.push(0x80);
+    while self.buffer.len() % 64 != 56 {
+      self.buffer.push(0);
+    }
+    self.buffer.extend_from_slice(&bit_len.to_be_bytes());
+
+    for block in self.buffer.chunks_exact(64) {
+      let block: [u8; 64] = block.try_into().unwrap();
+      process_block(&mut self.h, &block);
+    }
+
+    let mut out = [0u8; 32];
+    for (i, word) in self.h.iter().enumerate() {
+      out[i * 4..i * 4 + 4].copy_from_slice(&word.to_be_bytes());
+    }
+    out
+  }
+}
+
+const K: [u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Process a single 64-byte block, mutating the running state `h` in place.
+/// Shared by the one-shot [`sha256_hex`] path and the incremental [`Sha256`].
+fn process_block(h: &mut [u32; 8], block: &[u8; 64]) {
+  let mut w = [0u32; 64];
+  for (i, word) in block.chunks_exact(4).enumerate() {
+    w[i] = u32::from_be_bytes(word.try_into().unwrap());
+  }
+  for i in 16..64 {
+    let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+    let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+    w[i] = w[i - 16]
+      .wrapping_add(s0)
+      .wrapping_add(w[i - 7])
+      .wrapping_add(s1);
+  }
+
+  let (mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh) =
+    (h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7]);
+
+  for i in 0..64 {
+    let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+    let ch = (e & f) ^ ((!e) & g);
+    let temp1 = hh
+      .wrapping_add(s1)
+      .wrapping_add(ch)
+      .wrapping_add(K[i])
+      .wrapping_add(w[i]);
+    let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+    let maj = (a & b) ^ (a & c) ^ (b & c);
+    let temp2 = s0.wrapping_add(maj);
+
+    hh = g;
+    g = f;
+    f = e;
+    e = d.wrapping_add(temp1);
+    d = c;
+    c = b;
+    b = a;
+    a = temp1.wrapping_add(temp2);
+  }
+
+  h[0] = h[0].wrapping_add(a);
+  h[1] = h[1].wrapping_add(b);
+  h[2] = h[2].wrapping_add(c);
+  h[3] = h[3].wrapping_add(d);
+  h[4] = h[4].wrapping_add(e);
+  h[5] = h[5].wrapping_add(f);
+  h[6] = h[6].wrapping_add(g);
+  h[7] = h[7].wrapping_add(hh);
+}
+
+// --- MD5 (RFC 1321) ---
+//
+// Apple's `itms-services` OTA manifest still wants MD5 for its chunked
+// `md5s` integrity list, independent of the SHA-256 whole-file digest
+// above. Never use this for anything security-sensitive — it's here only
+// to satisfy that format.
+
+const MD5_S: [u32; 64] = [
+  7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14,
+  20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15, 21, 6,
+  10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+const MD5_K: [u32; 64] = [
+  0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+  0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+  0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+  0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+  0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+  0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+  0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+  0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+fn md5_process_block(state: &mut [u32; 4], block: &[u8; 64]) {
+  let mut m = [0u32; 16];
+  for (i, word) in block.chunks_exact(4).enumerate() {
+    m[i] = u32::from_le_bytes(word.try_into().unwrap());
+  }
+
+  let (mut a, mut b, mut c, mut d) = (state[0], state[1], state[2], state[3]);
+
+  for i in 0..64 {
+    let (f, g) = match i {
+      0..=15 => ((b & c) | (!b & d), i),
+      16..=31 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+      32..=47 => (b ^ c ^ d, (3 * i + 5) % 16),
+      _ => (c ^ (b | !d), (7 * i) % 16),
+    };
+    let f = f
+      .wrapping_add(a)
+      .wrapping_add(MD5_K[i])
+      .wrapping_add(m[g]);
+    a = d;
+    d = c;
+    c = b;
+    b = b.wrapping_add(f.rotate_left(MD5_S[i]));
+  }
+
+  state[0] = state[0].wrapping_add(a);
+  state[1] = state[1].wrapping_add(b);
+  state[2] = state[2].wrapping_add(c);
+  state[3] = state[3].wrapping_add(d);
+}
+
+/// One-shot MD5 digest of `data`, lowercase hex encoded.
+pub fn md5_hex(data: &[u8]) -> String {
+  let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+  let bit_len = (data.len() as u64).wrapping_mul(8);
+  let mut padded = data.to_vec();
+  padded.push(0x80);
+  while padded.len() % 64 != 56 {
+    padded.push(0);
+  }
+  padded.extend_from_slice(&bit_len.to_le_bytes());
+
+  for chunk in padded.chunks_exact(64) {
+    md5_process_block(&mut state, chunk.try_into().unwrap());
+  }
+
+  let mut out = [0u8; 16];
+  for (i, word) in state.iter().enumerate() {
+    out[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+  }
+  out.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Split `data` into consecutive `block_size`-byte chunks (the last one
+/// shorter) and MD5 each — the ordered, contiguous list Apple's OTA
+/// manifest expects as the `software-package` asset's `md5s` array.
+pub fn chunked_md5(data: &[u8], block_size: usize) -> Vec<String> {
+  data.chunks(block_size).map(md5_hex).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_sha256_empty() {
+    assert_eq!(
+      sha256_hex(b""),
+      "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+  }
+
+  #[test]
+  fn test_sha256_abc() {
+    // NIST test vector for "abc"
+    assert_eq!(
+      sha256_hex(b"abc"),
+      "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+  }
+
+  #[test]
+  fn test_build_and_verify_manifest_roundtrip() {
+    let data = b"the quick brown fox jumps over the lazy dog";
+    let manifest = build_manifest(data, &["SC_Info/App.sinf".to_string()]);
+    assert_eq!(manifest.size, data.len() as u64);
+    assert_eq!(manifest.injected_files, vec!["SC_Info/App.sinf".to_string()]);
+    assert!(verify(data, &manifest).is_ok());
+  }
+
+  #[test]
+  fn test_verify_detects_size_mismatch() {
+    let manifest = build_manifest(b"original", &[]);
+    let err = verify(b"short", &manifest).unwrap_err();
+    assert!(matches!(err, IntegrityError::SizeMismatch { .. }));
+  }
+
+  #[test]
+  fn test_verify_detects_hash_mismatch_same_size() {
+    let manifest = build_manifest(b"aaaaaaaa", &[]);
+    let err = verify(b"bbbbbbbb", &manifest).unwrap_err();
+    assert!(matches!(err, IntegrityError::HashMismatch { .. }));
+  }
+
+  #[test]
+  fn test_md5_empty() {
+    assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+  }
+
+  #[test]
+  fn test_md5_abc() {
+    assert_eq!(md5_hex(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+  }
+
+  #[test]
+  fn test_chunked_md5_single_chunk() {
+    let data = b"hello world";
+    let chunks = chunked_md5(data, 1024);
+    assert_eq!(chunks, vec![md5_hex(data)]);
+  }
+
+  #[test]
+  fn test_chunked_md5_splits_on_boundary() {
+    let data = vec![0u8; 25];
+    let chunks = chunked_md5(&data, 10);
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks[0], md5_hex(&data[0..10]));
+    assert_eq!(chunks[1], md5_hex(&data[10..20]));
+    assert_eq!(chunks[2], md5_hex(&data[20..25]));
+  }
+
+  #[test]
+  fn test_chunked_md5_empty_input() {
+    assert!(chunked_md5(b"", 10).is_empty());
+  }
+}
@@ -1,12 +1,49 @@
 use crate::types::Software;
 
-/// Build an iTunes OTA installation manifest plist XML.
+/// One app's software metadata and asset URLs, as taken by
+/// [`build_manifest_batch`].
+pub struct ManifestItem<'a> {
+  pub software: &'a Software,
+  pub payload_url: &'a str,
+  pub display_image_small_url: &'a str,
+  pub display_image_large_url: &'a str,
+  pub md5_size: Option<u64>,
+  pub md5s: Option<&'a [String]>,
+}
+
+/// Build an iTunes OTA installation manifest plist XML for a single app.
+/// When `md5_size` and `md5s` are both present, the `software-package`
+/// asset gets Apple's chunked-MD5 integrity keys so the device rejects a
+/// truncated or corrupted transfer instead of silently installing it.
 pub fn build_manifest(
   software: &Software,
   payload_url: &str,
   display_image_small_url: &str,
   display_image_large_url: &str,
+  md5_size: Option<u64>,
+  md5s: Option<&[String]>,
 ) -> String {
+  build_manifest_batch(&[ManifestItem {
+    software,
+    payload_url,
+    display_image_small_url,
+    display_image_large_url,
+    md5_size,
+    md5s,
+  }])
+}
+
+/// Build an iTunes OTA installation manifest plist XML with one `items`
+/// entry per app. The `itms-services` plist format accepts multiple items
+/// in a single manifest, so one `download-manifest` action queues every
+/// app in `items` for install instead of just one.
+pub fn build_manifest_batch(items: &[ManifestItem]) -> String {
+  let items_xml = items
+    .iter()
+    .map(build_manifest_item)
+    .collect::<Vec<_>>()
+    .join("\n");
+
   format!(
     r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
@@ -14,14 +51,37 @@ pub fn build_manifest(
 <dict>
     <key>items</key>
     <array>
-        <dict>
+{}
+    </array>
+</dict>
+</plist>"#,
+    items_xml,
+  )
+}
+
+fn build_manifest_item(item: &ManifestItem) -> String {
+  let integrity = match (item.md5_size, item.md5s) {
+    (Some(size), Some(digests)) if !digests.is_empty() => format!(
+      "\n                    <key>md5-size</key>\n                    <integer>{}</integer>\n                    <key>md5s</key>\n                    <array>\n{}\n                    </array>",
+      size,
+      digests
+        .iter()
+        .map(|d| format!("                        <string>{}</string>", escape_xml(d)))
+        .collect::<Vec<_>>()
+        .join("\n"),
+    ),
+    _ => String::new(),
+  };
+
+  format!(
+    r#"        <dict>
             <key>assets</key>
             <array>
                 <dict>
                     <key>kind</key>
                     <string>software-package</string>
                     <key>url</key>
-                    <string>{}</string>
+                    <string>{}</string>{}
                 </dict>
                 <dict>
                     <key>kind</key>
@@ -47,16 +107,14 @@ pub fn build_manifest(
                 <key>title</key>
                 <string>{}</string>
             </dict>
-        </dict>
-    </array>
-</dict>
-</plist>"#,
-    escape_xml(payload_url),
-    escape_xml(display_image_small_url),
-    escape_xml(display_image_large_url),
-    escape_xml(&software.bundle_id),
-    escape_xml(&software.version),
-    escape_xml(&software.name),
+        </dict>"#,
+    escape_xml(item.payload_url),
+    integrity,
+    escape_xml(item.display_image_small_url),
+    escape_xml(item.display_image_large_url),
+    escape_xml(&item.software.bundle_id),
+    escape_xml(&item.software.version),
+    escape_xml(&item.software.name),
   )
 }
 
@@ -121,6 +179,8 @@ mod tests {
       "https://example.com/payload.ipa",
       "https://example.com/small.png",
       "https://example.com/large.png",
+      None,
+      None,
     );
 
     assert!(xml.starts_with("<?xml"));
@@ -133,17 +193,108 @@ mod tests {
     assert!(xml.contains("<string>1.0</string>"));
     assert!(xml.contains("<string>Test App</string>"));
     assert!(xml.contains("<string>software</string>"));
+    assert!(!xml.contains("md5-size"));
+    assert!(!xml.contains("md5s"));
   }
 
   #[test]
   fn test_build_manifest_xml_escaping() {
     let mut sw = test_software();
     sw.name = "App & \"Friends\" <More>".into();
-    let xml = build_manifest(&sw, "https://a.com/b?x=1&y=2", "", "");
+    let xml = build_manifest(&sw, "https://a.com/b?x=1&y=2", "", "", None, None);
     assert!(xml.contains("App &amp; &quot;Friends&quot; &lt;More&gt;"));
     assert!(xml.contains("https://a.com/b?x=1&amp;y=2"));
   }
 
+  #[test]
+  fn test_build_manifest_includes_md5_integrity() {
+    let sw = test_software();
+    let digests = vec!["d41d8cd98f00b204e9800998ecf8427e".to_string(), "900150983cd24fb0d6963f7d28e17f72".to_string()];
+    let xml = build_manifest(
+      &sw,
+      "https://example.com/payload.ipa",
+      "",
+      "",
+      Some(10 * 1024 * 1024),
+      Some(&digests),
+    );
+
+    assert!(xml.contains("<key>md5-size</key>"));
+    assert!(xml.contains("<integer>10485760</integer>"));
+    assert!(xml.contains("<key>md5s</key>"));
+    assert!(xml.contains("<string>d41d8cd98f00b204e9800998ecf8427e</string>"));
+    assert!(xml.contains("<string>900150983cd24fb0d6963f7d28e17f72</string>"));
+  }
+
+  #[test]
+  fn test_build_manifest_omits_integrity_when_md5s_empty() {
+    let sw = test_software();
+    let xml = build_manifest(&sw, "https://example.com/payload.ipa", "", "", Some(10), Some(&[]));
+    assert!(!xml.contains("md5-size"));
+  }
+
+  #[test]
+  fn test_build_manifest_batch_emits_one_item_per_app() {
+    let sw_a = test_software();
+    let mut sw_b = test_software();
+    sw_b.bundle_id = "com.example.other".into();
+    sw_b.name = "Other App".into();
+
+    let xml = build_manifest_batch(&[
+      ManifestItem {
+        software: &sw_a,
+        payload_url: "https://example.com/a.ipa",
+        display_image_small_url: "",
+        display_image_large_url: "",
+        md5_size: None,
+        md5s: None,
+      },
+      ManifestItem {
+        software: &sw_b,
+        payload_url: "https://example.com/b.ipa",
+        display_image_small_url: "",
+        display_image_large_url: "",
+        md5_size: None,
+        md5s: None,
+      },
+    ]);
+
+    assert_eq!(xml.matches("<key>bundle-identifier</key>").count(), 2);
+    assert!(xml.contains("<string>com.example.app</string>"));
+    assert!(xml.contains("<string>com.example.other</string>"));
+    assert!(xml.contains("<string>https://example.com/a.ipa</string>"));
+    assert!(xml.contains("<string>https://example.com/b.ipa</string>"));
+  }
+
+  #[test]
+  fn test_build_manifest_batch_empty_items_is_still_valid_shell() {
+    let xml = build_manifest_batch(&[]);
+    assert!(xml.contains("<key>items</key>"));
+    assert!(!xml.contains("bundle-identifier"));
+  }
+
+  #[test]
+  fn test_build_manifest_batch_matches_single_item_build_manifest() {
+    let sw = test_software();
+    let single = build_manifest(
+      &sw,
+      "https://example.com/payload.ipa",
+      "https://example.com/small.png",
+      "https://example.com/large.png",
+      None,
+      None,
+    );
+    let batch = build_manifest_batch(&[ManifestItem {
+      software: &sw,
+      payload_url: "https://example.com/payload.ipa",
+      display_image_small_url: "https://example.com/small.png",
+      display_image_large_url: "https://example.com/large.png",
+      md5_size: None,
+      md5s: None,
+    }]);
+    assert_eq!(single, batch);
+  }
+
   #[test]
   fn test_white_png_valid() {
     assert_eq!(WHITE_PNG.len(), 70);
@@ -0,0 +1,330 @@
+//! iTunes Search/Lookup client support.
+//!
+//! Lets a caller create a download task from just a bundle ID (or track ID)
+//! instead of hand-assembling a [`Software`] blob. This module only builds
+//! request URLs and normalizes responses — the actual HTTP call is made by
+//! the platform-specific caller (Workers `fetch`, a native client, ...), the
+//! same split `bag.rs` and `search.rs` already use.
+
+use crate::integrity::sha256_hex;
+use crate::search::map_lookup_result;
+use crate::types::Software;
+use serde_json::Value;
+
+/// The public iTunes lookup endpoint.
+pub const LOOKUP_ENDPOINT: &str = "https://itunes.apple.com/lookup";
+
+/// Lookup request timeout in seconds.
+pub const LOOKUP_TIMEOUT_SECS: u64 = 10;
+
+/// Cached entries are considered fresh for this long by default.
+pub const DEFAULT_CACHE_TTL_SECS: u64 = 15 * 60;
+
+/// How a lookup identifies the app: by numeric track ID or by bundle ID.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LookupIdentifier {
+  TrackId(i64),
+  BundleId(String),
+}
+
+/// Build the lookup URL for an identifier in a given storefront.
+pub fn build_lookup_url(identifier: &LookupIdentifier, storefront: &str) -> String {
+  let country = urlencoding::encode(storefront);
+  match identifier {
+    LookupIdentifier::TrackId(id) => format!("{LOOKUP_ENDPOINT}?id={id}&country={country}"),
+    LookupIdentifier::BundleId(bundle_id) => {
+      format!(
+        "{LOOKUP_ENDPOINT}?bundleId={}&country={country}",
+        urlencoding::encode(bundle_id)
+      )
+    }
+  }
+}
+
+/// Resolve exactly-one-of `track_id`/`bundle_id` (as submitted on e.g.
+/// `CreateDownloadByIdentifierRequest`) into a [`LookupIdentifier`].
+pub fn identifier_from_parts(
+  track_id: Option<i64>,
+  bundle_id: Option<&str>,
+) -> Result<LookupIdentifier, String> {
+  match (track_id, bundle_id) {
+    (Some(id), None) => Ok(LookupIdentifier::TrackId(id)),
+    (None, Some(bundle_id)) => Ok(LookupIdentifier::BundleId(bundle_id.to_string())),
+    (None, None) => Err("Provide either trackId or bundleId".to_string()),
+    (Some(_), Some(_)) => Err("Provide only one of trackId or bundleId".to_string()),
+  }
+}
+
+/// Normalize a raw lookup response into a single [`Software`], regardless of
+/// which storefront answered. Returns an error (not `None`) so callers can
+/// tell "the app doesn't exist" apart from "we never asked".
+pub fn normalize_lookup_response(data: &Value) -> Result<Software, String> {
+  map_lookup_result(data).ok_or_else(|| "No matching app found for this identifier".to_string())
+}
+
+/// Cache key: the identifier string (track ID, bundle ID, or search term)
+/// plus storefront.
+pub type CacheKey = (String, String);
+
+/// Per-endpoint cache tuning, modeled on the per-route cache-control knobs in
+/// vaultwarden's header/caching fairing: how long a positive result stays
+/// fresh, and how long a negative ("no such app"/"no results") one is kept
+/// so repeated misses don't keep round-tripping to Apple. Lookup-by-id can
+/// cache longer since a track ID rarely changes owner; free-text search
+/// results shift more often (ranking, newly published apps) so they get a
+/// shorter window.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheControl {
+  pub fresh_ttl_secs: u64,
+  pub negative_ttl_secs: u64,
+}
+
+impl CacheControl {
+  fn ttl_for(self, is_negative: bool) -> u64 {
+    if is_negative {
+      self.negative_ttl_secs
+    } else {
+      self.fresh_ttl_secs
+    }
+  }
+}
+
+/// Cache-control for `id=`/`bundleId=` lookups.
+pub const LOOKUP_CACHE_CONTROL: CacheControl = CacheControl {
+  fresh_ttl_secs: DEFAULT_CACHE_TTL_SECS,
+  negative_ttl_secs: 60,
+};
+
+/// Cache-control for free-text search.
+pub const SEARCH_CACHE_CONTROL: CacheControl = CacheControl {
+  fresh_ttl_secs: 2 * 60,
+  negative_ttl_secs: 30,
+};
+
+/// Whether a cached entry should be served as-is, served while a
+/// revalidation fetch runs in the background, or treated as a total miss.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheFreshness {
+  /// Within TTL — serve straight from the cached entry.
+  Fresh,
+  /// Past TTL but still present — serve it while revalidating.
+  Stale,
+  /// Nothing cached for this key.
+  Miss,
+}
+
+/// One cached entry: the mapped value, a fingerprint of its canonical JSON
+/// (so a revalidation that comes back identical can just bump
+/// `fetched_at_secs` instead of rewriting the whole entry), and when it was
+/// fetched. `Serialize`/`Deserialize` so a platform caller can persist it to
+/// real storage (KV, a file, ...) under the key from [`cache_key`]/
+/// [`search_cache_key`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CacheEntry<T> {
+  pub value: T,
+  fingerprint: String,
+  fetched_at_secs: u64,
+  is_negative: bool,
+}
+
+fn fingerprint_of<T: serde::Serialize>(value: &T) -> String {
+  let canonical = serde_json::to_vec(value).unwrap_or_default();
+  sha256_hex(&canonical)
+}
+
+impl<T: serde::Serialize> CacheEntry<T> {
+  /// Build a fresh entry around the first fetch for this key.
+  pub fn new(value: T, is_negative: bool, fetched_at_secs: u64) -> Self {
+    let fingerprint = fingerprint_of(&value);
+    Self {
+      value,
+      fingerprint,
+      fetched_at_secs,
+      is_negative,
+    }
+  }
+
+  /// Classify this entry as fresh or stale per `control` (never `Miss` — a
+  /// caller without an entry at all is the `Miss` case, checked before this
+  /// is reached).
+  pub fn freshness(&self, now_secs: u64, control: CacheControl) -> CacheFreshness {
+    let ttl = control.ttl_for(self.is_negative);
+    if now_secs.saturating_sub(self.fetched_at_secs) <= ttl {
+      CacheFreshness::Fresh
+    } else {
+      CacheFreshness::Stale
+    }
+  }
+
+  /// Revalidate against a freshly fetched `value`: if its fingerprint is
+  /// unchanged from what's cached, the returned entry only differs in
+  /// `fetched_at_secs`; if it differs, the returned entry replaces this one
+  /// outright.
+  pub fn revalidated(&self, value: T, is_negative: bool, now_secs: u64) -> Self {
+    let fingerprint = fingerprint_of(&value);
+    Self {
+      value,
+      fingerprint,
+      fetched_at_secs: now_secs,
+      is_negative,
+    }
+  }
+}
+
+/// Build the cache key for a lookup identifier + storefront pair.
+pub fn cache_key(identifier: &LookupIdentifier, storefront: &str) -> CacheKey {
+  let id_part = match identifier {
+    LookupIdentifier::TrackId(id) => id.to_string(),
+    LookupIdentifier::BundleId(bundle_id) => bundle_id.clone(),
+  };
+  (id_part, storefront.to_ascii_lowercase())
+}
+
+/// Build the cache key for a free-text search term + storefront pair.
+/// Trims and lowercases the term so "Facebook", " facebook", and "FACEBOOK"
+/// share a cache entry.
+pub fn search_cache_key(term: &str, storefront: &str) -> CacheKey {
+  (term.trim().to_ascii_lowercase(), storefront.to_ascii_lowercase())
+}
+
+/// Storage key for a cached entry in a real key-value store (e.g. Workers
+/// KV). `kind` distinguishes the lookup and search caches so an identifier
+/// that happens to also look like a search term can't collide between them.
+pub fn cache_storage_key(kind: &str, key: &CacheKey) -> String {
+  format!("metacache:{kind}:{}:{}", key.0, key.1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_build_lookup_url_by_bundle_id() {
+    let url = build_lookup_url(&LookupIdentifier::BundleId("com.facebook.Facebook".into()), "us");
+    assert_eq!(
+      url,
+      "https://itunes.apple.com/lookup?bundleId=com.facebook.Facebook&country=us"
+    );
+  }
+
+  #[test]
+  fn test_build_lookup_url_by_track_id() {
+    let url = build_lookup_url(&LookupIdentifier::TrackId(284882215), "jp");
+    assert_eq!(url, "https://itunes.apple.com/lookup?id=284882215&country=jp");
+  }
+
+  #[test]
+  fn test_normalize_lookup_response_found() {
+    let data = json!({
+      "resultCount": 1,
+      "results": [{"trackId": 1, "bundleId": "com.test", "trackName": "Test"}]
+    });
+    let software = normalize_lookup_response(&data).unwrap();
+    assert_eq!(software.bundle_id, "com.test");
+  }
+
+  #[test]
+  fn test_normalize_lookup_response_not_found() {
+    let data = json!({"resultCount": 0, "results": []});
+    assert!(normalize_lookup_response(&data).is_err());
+  }
+
+  #[test]
+  fn test_cache_key_normalizes_storefront_case() {
+    let a = cache_key(&LookupIdentifier::BundleId("com.test".into()), "US");
+    let b = cache_key(&LookupIdentifier::BundleId("com.test".into()), "us");
+    assert_eq!(a, b);
+  }
+
+  fn sample_software() -> Software {
+    Software {
+      id: 1,
+      bundle_id: "com.test".into(),
+      name: "Test".into(),
+      version: "1.0".into(),
+      price: None,
+      artist_name: String::new(),
+      seller_name: String::new(),
+      description: String::new(),
+      average_user_rating: 0.0,
+      user_rating_count: 0,
+      artwork_url: String::new(),
+      screenshot_urls: vec![],
+      minimum_os_version: String::new(),
+      file_size_bytes: None,
+      release_date: String::new(),
+      release_notes: None,
+      formatted_price: None,
+      primary_genre_name: String::new(),
+    }
+  }
+
+  #[test]
+  fn test_search_cache_key_normalizes_term_and_case() {
+    let a = search_cache_key(" Facebook ", "US");
+    let b = search_cache_key("facebook", "us");
+    assert_eq!(a, b);
+  }
+
+  #[test]
+  fn test_entry_fresh_within_ttl() {
+    let entry = CacheEntry::new(Some(sample_software()), false, 1000);
+    assert_eq!(
+      entry.freshness(1100, LOOKUP_CACHE_CONTROL),
+      CacheFreshness::Fresh
+    );
+  }
+
+  #[test]
+  fn test_entry_stale_after_ttl_expires() {
+    let entry = CacheEntry::new(Some(sample_software()), false, 1000);
+    let stale_at = 1000 + LOOKUP_CACHE_CONTROL.fresh_ttl_secs + 1;
+    assert_eq!(
+      entry.freshness(stale_at, LOOKUP_CACHE_CONTROL),
+      CacheFreshness::Stale
+    );
+  }
+
+  #[test]
+  fn test_negative_entry_uses_shorter_ttl() {
+    let entry: CacheEntry<Option<Software>> = CacheEntry::new(None, true, 1000);
+    let after_fresh_ttl = 1000 + LOOKUP_CACHE_CONTROL.negative_ttl_secs + 1;
+    assert_eq!(
+      entry.freshness(after_fresh_ttl, LOOKUP_CACHE_CONTROL),
+      CacheFreshness::Stale
+    );
+    assert!(entry.value.is_none());
+  }
+
+  #[test]
+  fn test_revalidate_unchanged_value_bumps_timestamp_only() {
+    let entry = CacheEntry::new(Some(sample_software()), false, 1000);
+    let revalidated = entry.revalidated(Some(sample_software()), false, 5000);
+    assert_eq!(
+      revalidated.freshness(5000, LOOKUP_CACHE_CONTROL),
+      CacheFreshness::Fresh
+    );
+  }
+
+  #[test]
+  fn test_revalidate_changed_value_replaces_entry() {
+    let entry = CacheEntry::new(Some(sample_software()), false, 1000);
+
+    let mut updated = sample_software();
+    updated.version = "2.0".into();
+    let revalidated = entry.revalidated(Some(updated), false, 5000);
+
+    assert_eq!(revalidated.value.unwrap().version, "2.0");
+  }
+
+  #[test]
+  fn test_search_cache_stores_empty_results_as_negative() {
+    let entry: CacheEntry<Vec<Software>> = CacheEntry::new(vec![], true, 1000);
+    let after_negative_ttl = 1000 + SEARCH_CACHE_CONTROL.negative_ttl_secs + 1;
+    assert_eq!(
+      entry.freshness(after_negative_ttl, SEARCH_CACHE_CONTROL),
+      CacheFreshness::Stale
+    );
+  }
+}
@@ -26,6 +26,71 @@ pub fn get_string_array(dict: &Value, key: &str) -> Option<Vec<String>> {
   )
 }
 
+/// Extract a nested dictionary value from a plist dictionary.
+pub fn get_dict<'a>(dict: &'a Value, key: &str) -> Option<&'a Value> {
+  dict.as_dictionary()?.get(key)
+}
+
+/// Resolve an app's icon candidate file names from its Info.plist, preferring
+/// the modern `CFBundleIcons` -> `CFBundlePrimaryIcon` -> `CFBundleIconFiles`
+/// path and falling back to the legacy single `CFBundleIconFile` string.
+pub fn resolve_icon_file_names(info_plist: &Value) -> Vec<String> {
+  let from_icons = get_dict(info_plist, "CFBundleIcons")
+    .and_then(|icons| get_dict(icons, "CFBundlePrimaryIcon"))
+    .and_then(|primary| get_string_array(primary, "CFBundleIconFiles"))
+    .filter(|files| !files.is_empty());
+
+  if let Some(files) = from_icons {
+    return files;
+  }
+
+  get_string(info_plist, "CFBundleIconFile")
+    .map(|name| vec![name])
+    .unwrap_or_default()
+}
+
+/// Set a string value in a plist dictionary, inserting or replacing the key.
+/// No-op if `dict` isn't a dictionary.
+pub fn set_string(dict: &mut Value, key: &str, value: &str) {
+  if let Some(d) = dict.as_dictionary_mut() {
+    d.insert(key.to_string(), Value::String(value.to_string()));
+  }
+}
+
+/// Set a string array value in a plist dictionary, inserting or replacing
+/// the key. No-op if `dict` isn't a dictionary.
+pub fn set_array(dict: &mut Value, key: &str, values: &[String]) {
+  if let Some(d) = dict.as_dictionary_mut() {
+    let array = values.iter().cloned().map(Value::String).collect();
+    d.insert(key.to_string(), Value::Array(array));
+  }
+}
+
+/// Deep-merge `overlay` into `base`: a key present in both whose values are
+/// both dictionaries is merged recursively; every other key is overwritten
+/// wholesale by the overlay's value (arrays included — there's no per-array
+/// merge strategy yet). No-op if either side isn't a dictionary.
+pub fn merge(base: &mut Value, overlay: &Value) {
+  let (Some(base_dict), Some(overlay_dict)) = (base.as_dictionary_mut(), overlay.as_dictionary())
+  else {
+    return;
+  };
+
+  for (key, overlay_value) in overlay_dict.iter() {
+    let both_dicts = overlay_value.as_dictionary().is_some()
+      && base_dict
+        .get(key)
+        .map(|v| v.as_dictionary().is_some())
+        .unwrap_or(false);
+
+    if both_dicts {
+      merge(base_dict.get_mut(key).unwrap(), overlay_value);
+    } else {
+      base_dict.insert(key.clone(), overlay_value.clone());
+    }
+  }
+}
+
 /// Convert an XML plist string to binary plist data.
 pub fn xml_to_binary_plist(xml: &str) -> Result<Vec<u8>, String> {
   let value: Value =
@@ -98,4 +163,172 @@ mod tests {
     assert_eq!(get_string(&val, "a"), Some("b".into()));
     assert_eq!(get_string(&val, "missing"), None);
   }
+
+  #[test]
+  fn test_set_string_inserts_and_replaces() {
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.old.app</string>
+</dict>
+</plist>"#;
+    let mut val = parse_plist(xml).unwrap();
+
+    set_string(&mut val, "CFBundleIdentifier", "com.new.app");
+    set_string(&mut val, "CFBundleDisplayName", "New Name");
+
+    assert_eq!(get_string(&val, "CFBundleIdentifier"), Some("com.new.app".into()));
+    assert_eq!(get_string(&val, "CFBundleDisplayName"), Some("New Name".into()));
+  }
+
+  #[test]
+  fn test_set_array_inserts_and_replaces() {
+    let mut val = Value::Dictionary(Default::default());
+    set_array(&mut val, "CFBundleIconFiles", &["A".to_string(), "B".to_string()]);
+    assert_eq!(
+      get_string_array(&val, "CFBundleIconFiles"),
+      Some(vec!["A".to_string(), "B".to_string()])
+    );
+
+    set_array(&mut val, "CFBundleIconFiles", &["C".to_string()]);
+    assert_eq!(
+      get_string_array(&val, "CFBundleIconFiles"),
+      Some(vec!["C".to_string()])
+    );
+  }
+
+  #[test]
+  fn test_merge_scalar_conflict_overlay_wins() {
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.old.app</string>
+    <key>CFBundleVersion</key>
+    <string>1.0</string>
+</dict>
+</plist>"#;
+    let mut base = parse_plist(xml).unwrap();
+
+    let overlay_xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.new.app</string>
+</dict>
+</plist>"#;
+    let overlay = parse_plist(overlay_xml).unwrap();
+
+    merge(&mut base, &overlay);
+
+    assert_eq!(get_string(&base, "CFBundleIdentifier"), Some("com.new.app".into()));
+    assert_eq!(get_string(&base, "CFBundleVersion"), Some("1.0".into()));
+  }
+
+  #[test]
+  fn test_merge_recurses_into_nested_dictionaries() {
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIcons</key>
+    <dict>
+        <key>CFBundlePrimaryIcon</key>
+        <dict>
+            <key>CFBundleIconFiles</key>
+            <array>
+                <string>Old</string>
+            </array>
+        </dict>
+    </dict>
+    <key>CFBundleIdentifier</key>
+    <string>com.old.app</string>
+</dict>
+</plist>"#;
+    let mut base = parse_plist(xml).unwrap();
+
+    let overlay_xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIcons</key>
+    <dict>
+        <key>CFBundlePrimaryIcon</key>
+        <dict>
+            <key>CFBundleIconFiles</key>
+            <array>
+                <string>New</string>
+            </array>
+        </dict>
+    </dict>
+</dict>
+</plist>"#;
+    let overlay = parse_plist(overlay_xml).unwrap();
+
+    merge(&mut base, &overlay);
+
+    let icons = get_dict(&base, "CFBundleIcons").unwrap();
+    let primary = get_dict(icons, "CFBundlePrimaryIcon").unwrap();
+    assert_eq!(
+      get_string_array(primary, "CFBundleIconFiles"),
+      Some(vec!["New".to_string()])
+    );
+    // Untouched sibling key survives the merge.
+    assert_eq!(get_string(&base, "CFBundleIdentifier"), Some("com.old.app".into()));
+  }
+
+  #[test]
+  fn test_resolve_icon_file_names_prefers_cfbundleicons() {
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIconFile</key>
+    <string>Legacy</string>
+    <key>CFBundleIcons</key>
+    <dict>
+        <key>CFBundlePrimaryIcon</key>
+        <dict>
+            <key>CFBundleIconFiles</key>
+            <array>
+                <string>AppIcon60x60</string>
+                <string>AppIcon76x76</string>
+            </array>
+        </dict>
+    </dict>
+</dict>
+</plist>"#;
+
+    let val = parse_plist(xml).unwrap();
+    assert_eq!(
+      resolve_icon_file_names(&val),
+      vec!["AppIcon60x60".to_string(), "AppIcon76x76".to_string()]
+    );
+  }
+
+  #[test]
+  fn test_resolve_icon_file_names_falls_back_to_legacy_key() {
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleIconFile</key>
+    <string>Icon</string>
+</dict>
+</plist>"#;
+
+    let val = parse_plist(xml).unwrap();
+    assert_eq!(resolve_icon_file_names(&val), vec!["Icon".to_string()]);
+  }
+
+  #[test]
+  fn test_resolve_icon_file_names_missing_returns_empty() {
+    let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0">
+<dict>
+    <key>CFBundleExecutable</key>
+    <string>MyApp</string>
+</dict>
+</plist>"#;
+
+    let val = parse_plist(xml).unwrap();
+    assert!(resolve_icon_file_names(&val).is_empty());
+  }
 }
@@ -32,10 +32,50 @@ pub fn sanitize_path_segment(value: &str) -> Result<String, String> {
 
 // --- Download URL validation ---
 
-/// Check if a hostname ends with `.apple.com` (case-insensitive).
-fn is_apple_domain(host: &str) -> bool {
+/// One allowlist entry: a host suffix (e.g. `.apple.com`) and whether HTTPS
+/// is mandatory for hosts matching it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostAllowlistEntry {
+  pub suffix: String,
+  pub https_required: bool,
+}
+
+/// The default allowlist: only `*.apple.com`, HTTPS required. Matches the
+/// behavior before the allowlist became configurable.
+pub fn default_host_allowlist() -> Vec<HostAllowlistEntry> {
+  vec![HostAllowlistEntry {
+    suffix: ".apple.com".into(),
+    https_required: true,
+  }]
+}
+
+static HOST_ALLOWLIST: std::sync::OnceLock<Vec<HostAllowlistEntry>> = std::sync::OnceLock::new();
+
+/// Install a configuration-driven allowlist, e.g. parsed from environment
+/// config at startup. Only the first call takes effect; later calls are
+/// ignored so a long-lived Worker can't have its policy swapped mid-flight.
+pub fn configure_host_allowlist(entries: Vec<HostAllowlistEntry>) {
+  let _ = HOST_ALLOWLIST.set(entries);
+}
+
+/// The allowlist currently in effect: whatever was installed via
+/// [`configure_host_allowlist`], or [`default_host_allowlist`] if nothing
+/// was configured.
+pub fn effective_host_allowlist() -> Vec<HostAllowlistEntry> {
+  HOST_ALLOWLIST
+    .get()
+    .cloned()
+    .unwrap_or_else(default_host_allowlist)
+}
+
+fn matching_allowlist_entry<'a>(
+  host: &str,
+  entries: &'a [HostAllowlistEntry],
+) -> Option<&'a HostAllowlistEntry> {
   let lower = host.to_ascii_lowercase();
-  lower.ends_with(".apple.com")
+  entries
+    .iter()
+    .find(|entry| lower.ends_with(&entry.suffix.to_ascii_lowercase()))
 }
 
 /// Maximum download file size (4 GB).
@@ -44,23 +84,26 @@ pub const MAX_DOWNLOAD_SIZE: u64 = 4 * 1024 * 1024 * 1024;
 /// Download timeout in seconds.
 pub const DOWNLOAD_TIMEOUT_SECS: u64 = 10 * 60;
 
-/// Validate a download URL (must be HTTPS, *.apple.com, no IP addresses).
+/// Validate a download URL against the effective host allowlist, rejecting
+/// IP-literal hosts and non-HTTPS URLs unless an entry explicitly allows it.
 pub fn validate_download_url(url: &str) -> Result<(), String> {
   let parsed = url::Url::parse(url).map_err(|_| "Invalid download URL".to_string())?;
-
-  if parsed.scheme() != "https" {
-    return Err("Download URL must use HTTPS".into());
-  }
-
   let host = parsed.host_str().ok_or("Invalid download URL")?;
 
-  // Block IP addresses (check before domain match so error message is specific)
+  // Block IP addresses (check before the allowlist so the error is specific)
   if is_ip_address(host) {
     return Err("Download URL must not use IP addresses".into());
   }
 
-  if !is_apple_domain(host) {
-    return Err("Download URL must be from an Apple domain (*.apple.com)".into());
+  let allowlist = effective_host_allowlist();
+  let entry = matching_allowlist_entry(host, &allowlist)
+    .ok_or_else(|| "Download URL host is not in the allowlist".to_string())?;
+
+  if entry.https_required && parsed.scheme() != "https" {
+    return Err("Download URL must use HTTPS".into());
+  }
+  if !entry.https_required && parsed.scheme() != "https" && parsed.scheme() != "http" {
+    return Err("Download URL has an unsupported scheme".into());
   }
 
   Ok(())
@@ -161,10 +204,31 @@ mod tests {
     assert!(err.contains("HTTPS"));
   }
 
+  #[test]
+  fn test_default_host_allowlist_unchanged_behavior() {
+    let entries = default_host_allowlist();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].suffix, ".apple.com");
+    assert!(entries[0].https_required);
+  }
+
+  #[test]
+  fn test_matching_allowlist_entry() {
+    let entries = vec![
+      HostAllowlistEntry { suffix: ".apple.com".into(), https_required: true },
+      HostAllowlistEntry { suffix: ".mirror.example".into(), https_required: false },
+    ];
+    assert!(matching_allowlist_entry("cdn.apple.com", &entries).is_some());
+    assert!(matching_allowlist_entry("apple.com", &entries).is_none());
+    let mirror = matching_allowlist_entry("cache.mirror.example", &entries).unwrap();
+    assert!(!mirror.https_required);
+    assert!(matching_allowlist_entry("evil.com", &entries).is_none());
+  }
+
   #[test]
   fn test_validate_download_url_wrong_domain() {
     let err = validate_download_url("https://evil.com/file.ipa").unwrap_err();
-    assert!(err.contains("Apple domain"));
+    assert!(err.contains("allowlist"));
   }
 
   #[test]
@@ -35,6 +35,23 @@ pub struct Sinf {
   pub sinf: String, // base64 encoded
 }
 
+/// Identifies an R2 multipart upload that's still accepting parts, plus the
+/// parts already committed to it, so it can be resumed across invocations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagingUpload {
+  pub upload_id: String,
+  pub parts: Vec<UploadedPart>,
+}
+
+/// One committed part of an in-progress R2 multipart upload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedPart {
+  pub part_number: u16,
+  pub etag: String,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskStatus {
@@ -64,6 +81,46 @@ pub struct DownloadTask {
   pub error: Option<String>,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub file_path: Option<String>,
+  /// Bytes already persisted for this task, so a paused/crashed transfer can
+  /// resume with a `Range` request instead of starting over.
+  #[serde(default)]
+  pub downloaded_bytes: u64,
+  /// `ETag`/`Last-Modified` captured on the first response, used to detect
+  /// that the remote asset changed before trusting a resume.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub resume_etag: Option<String>,
+  /// In-progress R2 multipart upload backing a streamed download, so a later
+  /// resume attempt can append more parts instead of starting the upload
+  /// over. Cleared once the upload is completed (or abandoned for a restart).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub staging_upload: Option<StagingUpload>,
+  /// Recorded once the package is complete; checked again before serving or
+  /// re-using a cached file.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub integrity: Option<IntegrityManifest>,
+  /// How many retry attempts the current/last fetch of `download_url` took.
+  /// Stays 0 for a fetch that succeeded on the first try.
+  #[serde(default)]
+  pub retry_count: u32,
+  /// Carried over from the create request; checked against the freshly
+  /// computed hash before the downloaded bytes are stored.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub expected_sha256: Option<String>,
+  /// Chunk size (bytes) used to compute `md5s`, computed once at
+  /// package-completion time so the OTA manifest stays deterministic
+  /// across requests instead of being recomputed per request.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub md5_size: Option<u64>,
+  /// One lowercase-hex MD5 digest per consecutive `md5_size`-byte chunk of
+  /// the stored package, ordered and contiguous from offset 0.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub md5s: Option<Vec<String>>,
+  /// Carried over from the create request: an XML plist fragment merged
+  /// onto `Payload/*.app/Info.plist` before the package is stored, e.g. to
+  /// rebrand `CFBundleIdentifier`/`CFBundleDisplayName`/`CFBundleURLTypes`
+  /// for a resigned install. Cleared once applied, same as `sinfs`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub plist_overrides_xml: Option<String>,
   pub created_at: String,
 }
 
@@ -94,6 +151,18 @@ pub struct PackageInfo {
   pub created_at: String,
 }
 
+/// Integrity manifest recorded next to a completed package: the SHA-256 of
+/// the final IPA, its size, and which archive paths were injected. Lets a
+/// later read tell bit rot (hash mismatch) apart from a process killed
+/// mid-write (size mismatch / missing injected paths).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityManifest {
+  pub sha256: String,
+  pub size: u64,
+  pub injected_files: Vec<String>,
+}
+
 /// Request body for creating a download task
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -105,6 +174,43 @@ pub struct CreateDownloadRequest {
   pub sinfs: Vec<Sinf>,
   #[serde(rename = "iTunesMetadata")]
   pub itunes_metadata: Option<String>,
+  /// If set, the downloaded bytes must hash to this SHA-256 (lowercase hex)
+  /// or the task fails before anything is stored — lets a caller who
+  /// already knows the expected hash reject a tampered/truncated CDN
+  /// response up front.
+  #[serde(default)]
+  pub expected_sha256: Option<String>,
+  /// If set, an XML plist fragment merged onto the packaged app's
+  /// `Info.plist` (e.g. to rebrand the bundle identity) before it's stored.
+  /// This is the "override" installer concept from the addonscript manifest
+  /// schema — see `download_manager::patch_info_plist` for how it's applied.
+  #[serde(default)]
+  pub plist_overrides_xml: Option<String>,
+}
+
+/// Request body for creating a download task from an app identifier instead
+/// of a hand-assembled [`Software`] blob — exactly one of `track_id`/
+/// `bundle_id` must be set; `software` is resolved via the iTunes lookup
+/// endpoint (see the `metadata_client` module) before the task is created.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateDownloadByIdentifierRequest {
+  #[serde(default)]
+  pub track_id: Option<i64>,
+  #[serde(default)]
+  pub bundle_id: Option<String>,
+  #[serde(default)]
+  pub storefront: Option<String>,
+  pub account_hash: String,
+  #[serde(rename = "downloadURL")]
+  pub download_url: String,
+  pub sinfs: Vec<Sinf>,
+  #[serde(rename = "iTunesMetadata")]
+  pub itunes_metadata: Option<String>,
+  #[serde(default)]
+  pub expected_sha256: Option<String>,
+  #[serde(default)]
+  pub plist_overrides_xml: Option<String>,
 }
 
 impl DownloadTask {
@@ -206,6 +312,15 @@ mod tests {
       speed: "0 B/s".into(),
       error: None,
       file_path: Some("/data/packages/test.ipa".into()),
+      downloaded_bytes: 12345,
+      resume_etag: Some("\"abc123\"".into()),
+      staging_upload: None,
+      integrity: None,
+      retry_count: 0,
+      expected_sha256: None,
+      md5_size: None,
+      md5s: None,
+      plist_overrides_xml: None,
       created_at: "2024-01-01T00:00:00Z".into(),
     };
 
@@ -240,6 +355,15 @@ mod tests {
       speed: "0 B/s".into(),
       error: None,
       file_path: None,
+      downloaded_bytes: 0,
+      resume_etag: None,
+      staging_upload: None,
+      integrity: None,
+      retry_count: 0,
+      expected_sha256: None,
+      md5_size: None,
+      md5s: None,
+      plist_overrides_xml: None,
       created_at: "2024-01-01T00:00:00Z".into(),
     };
 
@@ -182,6 +182,189 @@ fn is_loopback(host: &str) -> bool {
     || host.ends_with(".localhost")
 }
 
+// --- Flow-controlled session state ---
+
+/// Starting send credit granted to a peer when a stream opens, advertised
+/// via the initial CONTINUE.
+const DEFAULT_BUFFER_REMAINING: u32 = 128;
+
+/// Per-stream flow-control state: how much outstanding send credit the
+/// peer currently holds for this stream.
+#[derive(Debug, Clone)]
+pub struct WispStream {
+  pub stream_id: u32,
+  pub stream_type: StreamType,
+  send_credit: u32,
+}
+
+impl WispStream {
+  fn new(stream_id: u32, stream_type: StreamType, initial_credit: u32) -> Self {
+    Self {
+      stream_id,
+      stream_type,
+      send_credit: initial_credit,
+    }
+  }
+
+  /// Credit currently available to the peer for this stream.
+  pub fn send_credit(&self) -> u32 {
+    self.send_credit
+  }
+
+  fn consume_credit(&mut self) -> Result<(), String> {
+    if self.send_credit == 0 {
+      return Err(format!(
+        "stream {} has no send credit remaining",
+        self.stream_id
+      ));
+    }
+    self.send_credit -= 1;
+    Ok(())
+  }
+
+  fn refill_credit(&mut self, buffer_remaining: u32) {
+    self.send_credit = buffer_remaining;
+  }
+}
+
+/// Owns the stream table for one Wisp connection and applies credit-based
+/// flow control across CONNECT/DATA/CONTINUE/CLOSE packets. A stream only
+/// exists in the table while open — CLOSE frees its state, and DATA for a
+/// stream that was never opened or already closed is rejected.
+#[derive(Debug, Default)]
+pub struct WispSession {
+  streams: std::collections::HashMap<u32, WispStream>,
+}
+
+impl WispSession {
+  pub fn new() -> Self {
+    Self {
+      streams: std::collections::HashMap::new(),
+    }
+  }
+
+  /// Open a new stream and return the initial CONTINUE packet the server
+  /// must send to grant the peer its starting send credit.
+  pub fn open_stream(&mut self, stream_id: u32, stream_type: StreamType) -> Result<Vec<u8>, String> {
+    if self.streams.contains_key(&stream_id) {
+      return Err(format!("stream {} is already open", stream_id));
+    }
+    self.streams.insert(
+      stream_id,
+      WispStream::new(stream_id, stream_type, DEFAULT_BUFFER_REMAINING),
+    );
+    Ok(make_continue_packet(stream_id, DEFAULT_BUFFER_REMAINING))
+  }
+
+  /// Record an outgoing DATA packet, decrementing the peer's remaining
+  /// credit. Fails for an unknown/closed stream or one with no credit left,
+  /// meaning the caller must stop sending and wait for a CONTINUE.
+  pub fn on_data_sent(&mut self, stream_id: u32) -> Result<(), String> {
+    let stream = self
+      .streams
+      .get_mut(&stream_id)
+      .ok_or_else(|| format!("DATA for unknown or closed stream {}", stream_id))?;
+    stream.consume_credit()
+  }
+
+  /// Apply a received CONTINUE, topping up the stream's send credit to the
+  /// advertised `buffer_remaining`.
+  pub fn on_continue_received(&mut self, stream_id: u32, buffer_remaining: u32) -> Result<(), String> {
+    let stream = self
+      .streams
+      .get_mut(&stream_id)
+      .ok_or_else(|| format!("CONTINUE for unknown or closed stream {}", stream_id))?;
+    stream.refill_credit(buffer_remaining);
+    Ok(())
+  }
+
+  /// Close a stream, freeing its flow-control state. The `reason` is not
+  /// retained — it is only meaningful to whichever side emits the CLOSE
+  /// packet on the wire.
+  pub fn close_stream(&mut self, stream_id: u32, _reason: CloseReason) -> Result<(), String> {
+    self
+      .streams
+      .remove(&stream_id)
+      .ok_or_else(|| format!("CLOSE for unknown stream {}", stream_id))?;
+    Ok(())
+  }
+
+  pub fn stream(&self, stream_id: u32) -> Option<&WispStream> {
+    self.streams.get(&stream_id)
+  }
+
+  pub fn is_open(&self, stream_id: u32) -> bool {
+    self.streams.contains_key(&stream_id)
+  }
+}
+
+// --- Incremental packet reader ---
+
+/// Turns a stream of appended bytes into complete Wisp packets, so a
+/// single WebSocket binary message carrying several packets — or a packet
+/// split across more than one message — is parsed incrementally instead of
+/// assuming one packet per buffer.
+///
+/// CONTINUE and CLOSE are fixed-size, so they're drained as soon as enough
+/// bytes are buffered and several can be coalesced out of one `feed` call.
+/// CONNECT and DATA carry no length prefix (their payload runs to the end
+/// of the message), so they're only emitted once the caller marks the
+/// message boundary with [`Self::end_of_message`].
+#[derive(Debug, Default)]
+pub struct WispFrameReader {
+  buf: Vec<u8>,
+}
+
+impl WispFrameReader {
+  pub fn new() -> Self {
+    Self { buf: Vec::new() }
+  }
+
+  /// Append newly received bytes and drain any complete fixed-size packets.
+  pub fn feed(&mut self, bytes: &[u8]) -> Vec<(WispPacketType, u32, Vec<u8>)> {
+    self.buf.extend_from_slice(bytes);
+    self.drain_fixed_packets()
+  }
+
+  /// Mark the end of the current message, flushing a buffered CONNECT or
+  /// DATA packet. Returns `None` if nothing is buffered or the buffered
+  /// bytes don't form a valid packet.
+  pub fn end_of_message(&mut self) -> Option<(WispPacketType, u32, Vec<u8>)> {
+    if self.buf.is_empty() {
+      return None;
+    }
+    let (packet_type, stream_id, payload) = parse_packet(&self.buf)?;
+    let result = (packet_type, stream_id, payload.to_vec());
+    self.buf.clear();
+    Some(result)
+  }
+
+  fn drain_fixed_packets(&mut self) -> Vec<(WispPacketType, u32, Vec<u8>)> {
+    let mut out = Vec::new();
+    loop {
+      if self.buf.len() < 5 {
+        break;
+      }
+      let Some(packet_type) = WispPacketType::from_u8(self.buf[0]) else {
+        break;
+      };
+      let fixed_len = match packet_type {
+        WispPacketType::Continue => 9,
+        WispPacketType::Close => 6,
+        _ => break,
+      };
+      if self.buf.len() < fixed_len {
+        break;
+      }
+      let (packet_type, stream_id, payload) =
+        parse_packet(&self.buf[..fixed_len]).expect("length checked above");
+      out.push((packet_type, stream_id, payload.to_vec()));
+      self.buf.drain(..fixed_len);
+    }
+    out
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -281,4 +464,113 @@ mod tests {
     assert_eq!(stream_id, 7);
     assert_eq!(payload, b"test data");
   }
+
+  #[test]
+  fn test_session_open_stream_grants_initial_credit() {
+    let mut session = WispSession::new();
+    let packet = session.open_stream(1, StreamType::Tcp).unwrap();
+    assert_eq!(packet[0], WispPacketType::Continue as u8);
+    assert_eq!(session.stream(1).unwrap().send_credit(), DEFAULT_BUFFER_REMAINING);
+  }
+
+  #[test]
+  fn test_session_open_stream_rejects_duplicate() {
+    let mut session = WispSession::new();
+    session.open_stream(1, StreamType::Tcp).unwrap();
+    assert!(session.open_stream(1, StreamType::Tcp).is_err());
+  }
+
+  #[test]
+  fn test_session_data_decrements_credit() {
+    let mut session = WispSession::new();
+    session.open_stream(1, StreamType::Tcp).unwrap();
+    session.on_data_sent(1).unwrap();
+    assert_eq!(session.stream(1).unwrap().send_credit(), DEFAULT_BUFFER_REMAINING - 1);
+  }
+
+  #[test]
+  fn test_session_data_exhausts_credit() {
+    let mut session = WispSession::new();
+    session.open_stream(1, StreamType::Tcp).unwrap();
+    for _ in 0..DEFAULT_BUFFER_REMAINING {
+      session.on_data_sent(1).unwrap();
+    }
+    assert_eq!(session.stream(1).unwrap().send_credit(), 0);
+    let err = session.on_data_sent(1).unwrap_err();
+    assert!(err.contains("no send credit"));
+  }
+
+  #[test]
+  fn test_session_continue_tops_up_credit() {
+    let mut session = WispSession::new();
+    session.open_stream(1, StreamType::Tcp).unwrap();
+    session.on_data_sent(1).unwrap();
+    session.on_continue_received(1, 64).unwrap();
+    assert_eq!(session.stream(1).unwrap().send_credit(), 64);
+  }
+
+  #[test]
+  fn test_session_data_rejected_for_unknown_stream() {
+    let mut session = WispSession::new();
+    assert!(session.on_data_sent(99).is_err());
+  }
+
+  #[test]
+  fn test_session_close_frees_stream_and_rejects_further_data() {
+    let mut session = WispSession::new();
+    session.open_stream(1, StreamType::Tcp).unwrap();
+    session.close_stream(1, CloseReason::Voluntary).unwrap();
+    assert!(!session.is_open(1));
+    assert!(session.on_data_sent(1).is_err());
+  }
+
+  #[test]
+  fn test_session_close_unknown_stream_errors() {
+    let mut session = WispSession::new();
+    assert!(session.close_stream(1, CloseReason::Voluntary).is_err());
+  }
+
+  #[test]
+  fn test_frame_reader_coalesces_multiple_fixed_packets() {
+    let mut buf = make_continue_packet(1, 10);
+    buf.extend_from_slice(&make_close_packet(2, CloseReason::Voluntary));
+
+    let mut reader = WispFrameReader::new();
+    let packets = reader.feed(&buf);
+    assert_eq!(packets.len(), 2);
+    assert_eq!(packets[0].0, WispPacketType::Continue);
+    assert_eq!(packets[0].1, 1);
+    assert_eq!(packets[1].0, WispPacketType::Close);
+    assert_eq!(packets[1].1, 2);
+  }
+
+  #[test]
+  fn test_frame_reader_waits_for_fragmented_fixed_packet() {
+    let packet = make_continue_packet(1, 10);
+    let mut reader = WispFrameReader::new();
+
+    let packets = reader.feed(&packet[..4]);
+    assert!(packets.is_empty());
+
+    let packets = reader.feed(&packet[4..]);
+    assert_eq!(packets.len(), 1);
+    assert_eq!(packets[0].0, WispPacketType::Continue);
+  }
+
+  #[test]
+  fn test_frame_reader_flushes_data_on_end_of_message() {
+    let mut reader = WispFrameReader::new();
+    assert!(reader.feed(&make_data_packet(3, b"hi")).is_empty());
+
+    let (ptype, stream_id, payload) = reader.end_of_message().unwrap();
+    assert_eq!(ptype, WispPacketType::Data);
+    assert_eq!(stream_id, 3);
+    assert_eq!(payload, b"hi");
+  }
+
+  #[test]
+  fn test_frame_reader_end_of_message_empty_returns_none() {
+    let mut reader = WispFrameReader::new();
+    assert!(reader.end_of_message().is_none());
+  }
 }
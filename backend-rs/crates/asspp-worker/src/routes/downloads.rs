@@ -1,12 +1,66 @@
-use asspp_core::download::validate_create_request;
+use asspp_core::capability::{self, CapabilityTokenWire};
+use asspp_core::download::{self, unix_now_secs, validate_create_request};
+use asspp_core::metadata_client;
 use asspp_core::security::validate_download_url;
-use asspp_core::types::{CreateDownloadRequest, TaskStatus};
+use asspp_core::types::{CreateDownloadByIdentifierRequest, CreateDownloadRequest, TaskStatus};
 use worker::*;
 
+use super::search::resolve_software;
 use super::{get_kv, get_query_param, get_r2};
 use crate::services::download_manager;
+use crate::services::kv_metadata::KvMetadata;
+
+/// This Worker's identity as a capability token audience.
+const WORKER_AUDIENCE: &str = "asspp-worker";
+
+/// Verify the `Authorization: Bearer <token>` header grants `download` on
+/// `bundle_id`/`version`, walking the delegation chain and resolving each
+/// issuer's signing key from KV as it goes.
+async fn authorize_create(
+  req: &Request,
+  kv: &KvMetadata,
+  bundle_id: &str,
+  version: &str,
+) -> std::result::Result<(), String> {
+  let header = req
+    .headers()
+    .get("Authorization")
+    .ok()
+    .flatten()
+    .ok_or_else(|| "Missing Authorization header".to_string())?;
+  let token_json = header
+    .strip_prefix("Bearer ")
+    .ok_or_else(|| "Authorization header must be a Bearer token".to_string())?;
+  let wire: CapabilityTokenWire =
+    serde_json::from_str(token_json).map_err(|e| format!("Malformed capability token: {e}"))?;
+  let token = wire.into_signed()?;
+
+  let mut issuers = Vec::new();
+  capability::collect_issuers(&token, &mut issuers);
+  let mut keys = std::collections::HashMap::new();
+  for issuer in &issuers {
+    if let Some(key) = kv
+      .get_capability_key(issuer)
+      .await
+      .map_err(|e| e.to_string())?
+    {
+      keys.insert(issuer.clone(), key);
+    }
+  }
+
+  capability::authorize(
+    &token,
+    unix_now_secs(),
+    WORKER_AUDIENCE,
+    &|issuer: &str| keys.get(issuer).cloned(),
+    &capability::bundle_resource(bundle_id),
+    "download",
+    version,
+  )
+}
 
 pub async fn create_download(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  super::init_host_allowlist(&ctx);
   let body: CreateDownloadRequest = req.json().await?;
 
   if let Err(msg) = validate_download_url(&body.download_url) {
@@ -20,6 +74,10 @@ pub async fn create_download(mut req: Request, ctx: RouteContext<()>) -> Result<
   let kv = get_kv(&ctx)?;
   let r2 = get_r2(&ctx)?;
 
+  if let Err(msg) = authorize_create(&req, &kv, &body.software.bundle_id, &body.software.version).await {
+    return Response::error(serde_json::json!({"error": msg}).to_string(), 401);
+  }
+
   let task = download_manager::create_task(&kv, &r2, body).await?;
 
   let file_exists = task.file_path.is_some();
@@ -29,6 +87,61 @@ pub async fn create_download(mut req: Request, ctx: RouteContext<()>) -> Result<
   Ok(resp)
 }
 
+/// Like [`create_download`], but `software` is resolved from a `trackId` or
+/// `bundleId` via the iTunes lookup endpoint (through [`resolve_software`]'s
+/// KV-backed cache) instead of being hand-assembled by the caller.
+pub async fn create_download_from_identifier(mut req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  super::init_host_allowlist(&ctx);
+  let body: CreateDownloadByIdentifierRequest = req.json().await?;
+
+  if let Err(msg) = validate_download_url(&body.download_url) {
+    return Response::error(serde_json::json!({"error": msg}).to_string(), 400);
+  }
+
+  let identifier = match metadata_client::identifier_from_parts(body.track_id, body.bundle_id.as_deref()) {
+    Ok(identifier) => identifier,
+    Err(msg) => return Response::error(serde_json::json!({"error": msg}).to_string(), 400),
+  };
+  let storefront = body.storefront.clone().unwrap_or_else(|| "us".to_string());
+
+  let kv = get_kv(&ctx)?;
+  let software = match resolve_software(&kv, &identifier, &storefront).await? {
+    Some(software) => software,
+    None => {
+      return Response::error(
+        serde_json::json!({"error": "No matching app found for this identifier"}).to_string(),
+        404,
+      )
+    }
+  };
+
+  let full_request = download::resolve_create_request(body, software);
+
+  if let Err(msg) = validate_create_request(&full_request) {
+    return Response::error(serde_json::json!({"error": msg}).to_string(), 400);
+  }
+
+  let r2 = get_r2(&ctx)?;
+  if let Err(msg) = authorize_create(
+    &req,
+    &kv,
+    &full_request.software.bundle_id,
+    &full_request.software.version,
+  )
+  .await
+  {
+    return Response::error(serde_json::json!({"error": msg}).to_string(), 401);
+  }
+
+  let task = download_manager::create_task(&kv, &r2, full_request).await?;
+
+  let file_exists = task.file_path.is_some();
+  let sanitized = task.sanitize(file_exists);
+  let mut resp = Response::from_json(&sanitized)?;
+  resp = resp.with_status(201);
+  Ok(resp)
+}
+
 pub async fn list_downloads(req: Request, ctx: RouteContext<()>) -> Result<Response> {
   let url = req.url()?;
   let hashes_param = get_query_param(&url, "accountHashes").unwrap_or_default();
@@ -151,6 +264,7 @@ pub async fn pause_download(req: Request, ctx: RouteContext<()>) -> Result<Respo
 }
 
 pub async fn resume_download(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  super::init_host_allowlist(&ctx);
   let url = req.url()?;
   let id = ctx.param("id").unwrap_or(&String::new()).clone();
   let account_hash = get_query_param(&url, "accountHash").unwrap_or_default();
@@ -163,7 +277,7 @@ pub async fn resume_download(req: Request, ctx: RouteContext<()>) -> Result<Resp
   }
 
   let kv = get_kv(&ctx)?;
-  let task = match kv.get_task(&id).await? {
+  let mut task = match kv.get_task(&id).await? {
     Some(t) => t,
     None => return Response::error(serde_json::json!({"error": "Download not found"}).to_string(), 404),
   };
@@ -179,15 +293,11 @@ pub async fn resume_download(req: Request, ctx: RouteContext<()>) -> Result<Resp
     );
   }
 
-  // On Workers, resume re-triggers download
+  // Continues `task.download_url` in place: sends a `Range` request for
+  // whatever's already staged (`task.downloaded_bytes`/`staging_upload`)
+  // instead of re-downloading the whole package from scratch.
   let r2 = get_r2(&ctx)?;
-  download_manager::create_task(&kv, &r2, asspp_core::types::CreateDownloadRequest {
-    software: task.software.clone(),
-    account_hash: task.account_hash.clone(),
-    download_url: task.download_url.clone(),
-    sinfs: task.sinfs.clone(),
-    itunes_metadata: task.itunes_metadata.clone(),
-  }).await?;
+  download_manager::resume_task(&kv, &r2, &mut task).await?;
 
   let updated = kv.get_task(&id).await?.unwrap_or(task);
   let sanitized = updated.sanitize(updated.file_path.is_some());
@@ -1,8 +1,11 @@
-use asspp_core::manifest::{build_manifest, WHITE_PNG};
-use asspp_core::types::TaskStatus;
+use asspp_core::download::{self, RangeOutcome};
+use asspp_core::manifest::{build_manifest, build_manifest_batch, ManifestItem, WHITE_PNG};
+use asspp_core::types::{DownloadTask, TaskStatus};
 use worker::*;
 
 use super::{get_kv, get_r2};
+use crate::services::kv_metadata::KvMetadata;
+use crate::services::r2_storage::R2Storage;
 
 fn get_base_url(req: &Request) -> String {
   let url = req.url().ok();
@@ -30,7 +33,14 @@ pub async fn manifest(req: Request, ctx: RouteContext<()>) -> Result<Response> {
   let small_icon_url = format!("{}/api/install/{}/icon-small.png", base_url, id);
   let large_icon_url = format!("{}/api/install/{}/icon-large.png", base_url, id);
 
-  let xml = build_manifest(&task.software, &payload_url, &small_icon_url, &large_icon_url);
+  let xml = build_manifest(
+    &task.software,
+    &payload_url,
+    &small_icon_url,
+    &large_icon_url,
+    task.md5_size,
+    task.md5s.as_deref(),
+  );
 
   let headers = Headers::new();
   headers.set("Content-Type", "application/xml")?;
@@ -58,27 +68,236 @@ pub async fn install_url(req: Request, ctx: RouteContext<()>) -> Result<Response
   }))
 }
 
-pub async fn payload(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+/// Resolve a group id into its member tasks' urls, partitioned into the
+/// tasks that are `Completed` (with the payload/icon URLs to cite for each)
+/// and the ids that were skipped because the task isn't done yet, failed,
+/// or doesn't exist.
+async fn resolve_completed_group(
+  kv: &KvMetadata,
+  base_url: &str,
+  group_id: &str,
+) -> Result<(Vec<DownloadTask>, Vec<(String, String, String)>, Vec<String>)> {
+  let task_ids = kv.get_task_group(group_id).await?.unwrap_or_default();
+
+  let mut tasks = Vec::new();
+  let mut urls = Vec::new();
+  let mut skipped = Vec::new();
+
+  for task_id in &task_ids {
+    match kv.get_task(task_id).await? {
+      Some(t) if t.status == TaskStatus::Completed && t.file_path.is_some() => {
+        urls.push((
+          format!("{}/api/install/{}/payload.ipa", base_url, task_id),
+          format!("{}/api/install/{}/icon-small.png", base_url, task_id),
+          format!("{}/api/install/{}/icon-large.png", base_url, task_id),
+        ));
+        tasks.push(t);
+      }
+      _ => skipped.push(task_id.clone()),
+    }
+  }
+
+  Ok((tasks, urls, skipped))
+}
+
+/// Combined manifest for every `Completed` task in a group, letting one
+/// `itms-services` action install the whole batch instead of one app at a
+/// time. Tasks that aren't `Completed` are silently left out here; call
+/// `group_install_url` to see which ids were skipped.
+pub async fn group_manifest(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  let group_id = ctx.param("id").unwrap_or(&String::new()).clone();
+  let kv = get_kv(&ctx)?;
+  let base_url = get_base_url(&req);
+  let (tasks, urls, _skipped) = resolve_completed_group(&kv, &base_url, &group_id).await?;
+
+  let items: Vec<ManifestItem> = tasks
+    .iter()
+    .zip(urls.iter())
+    .map(|(task, (payload_url, small_icon_url, large_icon_url))| ManifestItem {
+      software: &task.software,
+      payload_url,
+      display_image_small_url: small_icon_url,
+      display_image_large_url: large_icon_url,
+      md5_size: task.md5_size,
+      md5s: task.md5s.as_deref(),
+    })
+    .collect();
+
+  let xml = build_manifest_batch(&items);
+
+  let headers = Headers::new();
+  headers.set("Content-Type", "application/xml")?;
+  Ok(Response::ok(xml)?.with_headers(headers))
+}
+
+/// `itms-services://` install URL for a group, plus the ids that were
+/// skipped because they weren't `Completed`.
+pub async fn group_install_url(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  let group_id = ctx.param("id").unwrap_or(&String::new()).clone();
+  let kv = get_kv(&ctx)?;
+  let base_url = get_base_url(&req);
+  let (tasks, _urls, skipped) = resolve_completed_group(&kv, &base_url, &group_id).await?;
+
+  if tasks.is_empty() {
+    return Response::error(
+      serde_json::json!({"error": "No completed packages in group", "skipped": skipped}).to_string(),
+      404,
+    );
+  }
+
+  let manifest_url = format!("{}/api/install/group/{}/manifest.plist", base_url, group_id);
+  let install_url_str = format!(
+    "itms-services://?action=download-manifest&url={}",
+    urlencoding::encode(&manifest_url)
+  );
+
+  Response::from_json(&serde_json::json!({
+    "installUrl": install_url_str,
+    "manifestUrl": manifest_url,
+    "skipped": skipped,
+  }))
+}
+
+/// `ETag` for a completed task's payload: the recorded SHA-256 when we have
+/// one, else the R2 key itself. Used both to answer conditional requests and
+/// as the edge cache's revalidation key.
+fn payload_etag(task: &DownloadTask, r2_key: &str) -> String {
+  match task.integrity.as_ref() {
+    Some(manifest) => format!("\"{}\"", manifest.sha256),
+    None => format!("\"{r2_key}\""),
+  }
+}
+
+fn payload_cache_key(r2_key: &str) -> String {
+  format!("https://asspp-payload-cache.internal/{r2_key}")
+}
+
+/// Outcome of fetching the full stored package and checking it against its
+/// integrity manifest: either the verified bytes, or the `409` response to
+/// return because the stored object no longer matches. Shared by the full
+/// (`200`) and partial (`206`) response paths so a `Range` request — including
+/// a full-length single range — can't skip the check the 200 path has always
+/// run.
+enum VerifiedPayload {
+  Ok(Vec<u8>),
+  Failed(Response),
+}
+
+async fn fetch_verified_payload(
+  kv: &KvMetadata,
+  r2: &R2Storage,
+  r2_key: &str,
+  task: &mut DownloadTask,
+) -> Result<VerifiedPayload> {
+  let data = r2
+    .get(r2_key)
+    .await?
+    .ok_or_else(|| Error::RustError("File not found in R2".into()))?;
+
+  if crate::services::download_manager::verify_integrity(kv, task, &data)
+    .await
+    .is_err()
+  {
+    let resp = Response::error(
+      serde_json::json!({"error": "Package failed integrity verification"}).to_string(),
+      409,
+    )?;
+    return Ok(VerifiedPayload::Failed(resp));
+  }
+
+  Ok(VerifiedPayload::Ok(data))
+}
+
+/// Fetch the full package from R2 (pulling through the Workers edge cache
+/// keyed by the R2 object key so repeat installs of the same task don't
+/// re-read R2), verify its integrity, and build a `200` response.
+async fn full_payload_response(
+  kv: &KvMetadata,
+  r2: &R2Storage,
+  r2_key: &str,
+  task: &mut DownloadTask,
+  etag: &str,
+) -> Result<Response> {
+  let cache = Cache::default();
+  let cache_key = payload_cache_key(r2_key);
+  if let Some(cached) = cache.get(cache_key.as_str(), true).await? {
+    return Ok(cached);
+  }
+
+  let data = match fetch_verified_payload(kv, r2, r2_key, task).await? {
+    VerifiedPayload::Failed(resp) => return Ok(resp),
+    VerifiedPayload::Ok(data) => data,
+  };
+
+  let headers = Headers::new();
+  headers.set("Content-Type", "application/octet-stream")?;
+  headers.set("Content-Length", &data.len().to_string())?;
+  headers.set("Accept-Ranges", "bytes")?;
+  headers.set("ETag", etag)?;
+
+  let mut response = Response::from_bytes(data)?.with_headers(headers);
+  cache.put(cache_key.as_str(), response.cloned()?).await?;
+  Ok(response)
+}
+
+pub async fn payload(req: Request, ctx: RouteContext<()>) -> Result<Response> {
   let id = ctx.param("id").unwrap_or(&String::new()).clone();
   let kv = get_kv(&ctx)?;
-  let task = match kv.get_task(&id).await? {
+  let mut task = match kv.get_task(&id).await? {
     Some(t) if t.status == TaskStatus::Completed => t,
     _ => return Response::error(serde_json::json!({"error": "Package not found"}).to_string(), 404),
   };
 
-  let r2_key = task.file_path.as_ref().ok_or_else(|| {
-    Error::RustError("No file path".into())
-  })?;
-
+  let r2_key = task
+    .file_path
+    .clone()
+    .ok_or_else(|| Error::RustError("No file path".into()))?;
   let r2 = get_r2(&ctx)?;
-  let data = r2.get(r2_key).await?.ok_or_else(|| {
-    Error::RustError("File not found in R2".into())
-  })?;
+  let etag = payload_etag(&task, &r2_key);
 
-  let headers = Headers::new();
-  headers.set("Content-Type", "application/octet-stream")?;
-  headers.set("Content-Length", &data.len().to_string())?;
-  Ok(Response::from_bytes(data)?.with_headers(headers))
+  let range_header = req.headers().get("Range")?;
+  let if_range_header = req.headers().get("If-Range")?;
+  if range_header.is_none() || !download::if_range_satisfied(if_range_header.as_deref(), &etag) {
+    return full_payload_response(&kv, &r2, &r2_key, &mut task, &etag).await;
+  }
+
+  let total_size = match task.integrity.as_ref().map(|m| m.size) {
+    Some(size) => size,
+    None => r2
+      .size(&r2_key)
+      .await?
+      .ok_or_else(|| Error::RustError("File not found in R2".into()))?,
+  };
+
+  match download::parse_range_header(range_header.as_deref(), total_size) {
+    RangeOutcome::FullResponse => full_payload_response(&kv, &r2, &r2_key, &mut task, &etag).await,
+    RangeOutcome::Unsatisfiable => {
+      let headers = Headers::new();
+      headers.set("Content-Range", &format!("bytes */{total_size}"))?;
+      Ok(Response::error("Range Not Satisfiable", 416)?.with_headers(headers))
+    }
+    RangeOutcome::Partial(range) => {
+      let data = match fetch_verified_payload(&kv, &r2, &r2_key, &mut task).await? {
+        VerifiedPayload::Failed(resp) => return Ok(resp),
+        VerifiedPayload::Ok(data) => data,
+      };
+      let slice = data
+        .get(range.start as usize..=range.end as usize)
+        .ok_or_else(|| Error::RustError("Range out of bounds".into()))?;
+
+      let headers = Headers::new();
+      headers.set("Content-Type", "application/octet-stream")?;
+      headers.set("Content-Length", &slice.len().to_string())?;
+      headers.set("Content-Range", &range.content_range_header(total_size))?;
+      headers.set("Accept-Ranges", "bytes")?;
+      headers.set("ETag", &etag)?;
+      Ok(
+        Response::from_bytes(slice.to_vec())?
+          .with_headers(headers)
+          .with_status(206),
+      )
+    }
+  }
 }
 
 fn white_png_response() -> Result<Response> {
@@ -88,10 +307,30 @@ fn white_png_response() -> Result<Response> {
   Ok(Response::from_bytes(WHITE_PNG.to_vec())?.with_headers(headers))
 }
 
-pub async fn icon_small(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
-  white_png_response()
+async fn icon_response(ctx: RouteContext<()>) -> Result<Response> {
+  let id = ctx.param("id").unwrap_or(&String::new()).clone();
+  let kv = get_kv(&ctx)?;
+  let task = match kv.get_task(&id).await? {
+    Some(t) if t.status == TaskStatus::Completed && t.file_path.is_some() => t,
+    _ => return white_png_response(),
+  };
+
+  let r2 = get_r2(&ctx)?;
+  match crate::services::download_manager::get_or_convert_icon(&r2, &task).await {
+    Ok(Some(png)) => {
+      let headers = Headers::new();
+      headers.set("Content-Type", "image/png")?;
+      headers.set("Content-Length", &png.len().to_string())?;
+      Ok(Response::from_bytes(png)?.with_headers(headers))
+    }
+    _ => white_png_response(),
+  }
+}
+
+pub async fn icon_small(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  icon_response(ctx).await
 }
 
-pub async fn icon_large(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
-  white_png_response()
+pub async fn icon_large(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  icon_response(ctx).await
 }
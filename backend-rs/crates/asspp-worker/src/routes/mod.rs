@@ -24,3 +24,47 @@ pub fn get_query_param(url: &url::Url, key: &str) -> Option<String> {
     .find(|(k, _)| k == key)
     .map(|(_, v)| v.to_string())
 }
+
+/// Install the download-host allowlist from the `HOST_ALLOWLIST` binding
+/// (comma-separated `suffix[:http]` entries, e.g.
+/// `.apple.com,.mirror.example:http` to additionally allow a plain-HTTP
+/// mirror) via `security::configure_host_allowlist`, falling back to the
+/// built-in `*.apple.com`-only default when the binding is unset or empty.
+///
+/// Workers have no single startup hook to run this once at boot, and
+/// `configure_host_allowlist` itself only takes effect on its first call
+/// per isolate — so call this defensively at the top of any route that's
+/// about to validate a download URL; later calls (including from other
+/// concurrent requests in the same isolate) are no-ops.
+pub fn init_host_allowlist(ctx: &RouteContext<()>) {
+  let Ok(raw) = ctx.var("HOST_ALLOWLIST").map(|v| v.to_string()) else {
+    return;
+  };
+  let entries = parse_host_allowlist_var(&raw);
+  if !entries.is_empty() {
+    asspp_core::security::configure_host_allowlist(entries);
+  }
+}
+
+fn parse_host_allowlist_var(raw: &str) -> Vec<asspp_core::security::HostAllowlistEntry> {
+  raw
+    .split(',')
+    .filter_map(|part| {
+      let part = part.trim();
+      if part.is_empty() {
+        return None;
+      }
+      let (suffix, https_required) = match part.split_once(':') {
+        Some((suffix, flag)) => (suffix, flag.trim() != "http"),
+        None => (part, true),
+      };
+      if suffix.is_empty() {
+        return None;
+      }
+      Some(asspp_core::security::HostAllowlistEntry {
+        suffix: suffix.to_ascii_lowercase(),
+        https_required,
+      })
+    })
+    .collect()
+}
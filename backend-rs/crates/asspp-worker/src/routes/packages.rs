@@ -56,7 +56,7 @@ pub async fn download_file(req: Request, ctx: RouteContext<()>) -> Result<Respon
   }
 
   let kv = get_kv(&ctx)?;
-  let task = match kv.get_task(&id).await? {
+  let mut task = match kv.get_task(&id).await? {
     Some(t) if t.status == TaskStatus::Completed => t,
     _ => return Response::error(serde_json::json!({"error": "Package not found"}).to_string(), 404),
   };
@@ -65,15 +65,25 @@ pub async fn download_file(req: Request, ctx: RouteContext<()>) -> Result<Respon
     return Response::error(serde_json::json!({"error": "Access denied"}).to_string(), 403);
   }
 
-  let r2_key = task.file_path.as_ref().ok_or_else(|| {
+  let r2_key = task.file_path.clone().ok_or_else(|| {
     Error::RustError("No file path".into())
   })?;
 
   let r2 = get_r2(&ctx)?;
-  let data = r2.get(r2_key).await?.ok_or_else(|| {
+  let data = r2.get(&r2_key).await?.ok_or_else(|| {
     Error::RustError("File not found in R2".into())
   })?;
 
+  if crate::services::download_manager::verify_integrity(&kv, &mut task, &data)
+    .await
+    .is_err()
+  {
+    return Response::error(
+      serde_json::json!({"error": "Package failed integrity verification"}).to_string(),
+      409,
+    );
+  }
+
   let safe_name = sanitize_filename(&task.software.name);
   let safe_version = sanitize_filename(&task.software.version);
   let filename = format!("{}_{}.ipa", safe_name, safe_version);
@@ -0,0 +1,154 @@
+use asspp_core::download::unix_now_secs;
+use asspp_core::metadata_client::{
+  self, CacheEntry, CacheFreshness, LookupIdentifier, LOOKUP_CACHE_CONTROL, SEARCH_CACHE_CONTROL,
+};
+use asspp_core::search::map_search_results;
+use asspp_core::types::Software;
+use worker::*;
+
+use super::{get_kv, get_query_param};
+
+fn storefront_param(url: &url::Url) -> String {
+  get_query_param(url, "country").unwrap_or_else(|| "us".to_string())
+}
+
+async fn fetch_itunes_json(url: &str) -> Result<serde_json::Value> {
+  let request = Request::new(url, Method::Get)?;
+  let mut resp = Fetch::Request(request).send().await?;
+  if resp.status_code() >= 400 {
+    return Err(Error::RustError(format!(
+      "iTunes request failed: HTTP {}",
+      resp.status_code()
+    )));
+  }
+  resp.json().await
+}
+
+fn software_response(software: Option<&Software>) -> Result<Response> {
+  match software {
+    Some(sw) => Response::from_json(sw),
+    None => Response::error(
+      serde_json::json!({"error": "No matching app found for this identifier"}).to_string(),
+      404,
+    ),
+  }
+}
+
+/// Resolve a single app's metadata for `identifier`/`storefront`, serving
+/// straight from the KV-backed cache when fresh and otherwise conditionally
+/// revalidating against Apple, falling back to a stale cached value if Apple
+/// errors rather than failing the caller outright. `Ok(None)` means Apple
+/// (or the cache) confirmed there's no such app, as distinct from the `Err`
+/// returned when neither a fresh answer nor a usable cached one is
+/// available. Shared by the [`lookup`] route and
+/// `downloads::create_download_from_identifier`, which both need the same
+/// cache/revalidate dance to turn an identifier into a [`Software`].
+pub(crate) async fn resolve_software(
+  kv: &crate::services::kv_metadata::KvMetadata,
+  identifier: &LookupIdentifier,
+  storefront: &str,
+) -> Result<Option<Software>> {
+  let key = metadata_client::cache_key(identifier, storefront);
+  let now = unix_now_secs();
+  let cached = kv.get_lookup_cache_entry(&key).await?;
+
+  if let Some(entry) = &cached {
+    if entry.freshness(now, LOOKUP_CACHE_CONTROL) == CacheFreshness::Fresh {
+      return Ok(entry.value.clone());
+    }
+  }
+
+  let fetch_url = metadata_client::build_lookup_url(identifier, storefront);
+  match fetch_itunes_json(&fetch_url).await {
+    Ok(data) => {
+      let software = metadata_client::normalize_lookup_response(&data).ok();
+      let is_negative = software.is_none();
+      let entry = match cached {
+        Some(existing) => existing.revalidated(software.clone(), is_negative, now),
+        None => CacheEntry::new(software.clone(), is_negative, now),
+      };
+      kv.put_lookup_cache_entry(&key, &entry).await?;
+      Ok(software)
+    }
+    // Apple is down or erroring but we still have a (stale) cached answer —
+    // serve that instead of failing a request we could otherwise satisfy.
+    Err(e) => match cached {
+      Some(entry) => Ok(entry.value),
+      None => Err(e),
+    },
+  }
+}
+
+/// `GET /api/search/lookup?id=...`|`bundleId=...&country=...` — resolve a
+/// single app's metadata.
+pub async fn lookup(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  let url = req.url()?;
+  let storefront = storefront_param(&url);
+
+  let identifier = if let Some(id) = get_query_param(&url, "id") {
+    let track_id: i64 = id
+      .parse()
+      .map_err(|_| Error::RustError("id must be numeric".into()))?;
+    LookupIdentifier::TrackId(track_id)
+  } else if let Some(bundle_id) = get_query_param(&url, "bundleId") {
+    LookupIdentifier::BundleId(bundle_id)
+  } else {
+    return Response::error(
+      serde_json::json!({"error": "Provide either id or bundleId"}).to_string(),
+      400,
+    );
+  };
+
+  let kv = get_kv(&ctx)?;
+  let software = resolve_software(&kv, &identifier, &storefront).await?;
+  software_response(software.as_ref())
+}
+
+/// `GET /api/search?term=...&country=...` — free-text search, same
+/// KV-backed cache/revalidation shape as [`lookup`] but keyed by the search
+/// term and caching an empty result list as a negative entry.
+pub async fn search(req: Request, ctx: RouteContext<()>) -> Result<Response> {
+  let url = req.url()?;
+  let storefront = storefront_param(&url);
+  let term = get_query_param(&url, "term").unwrap_or_default();
+  if term.trim().is_empty() {
+    return Response::error(
+      serde_json::json!({"error": "Missing term parameter"}).to_string(),
+      400,
+    );
+  }
+
+  let kv = get_kv(&ctx)?;
+  let key = metadata_client::search_cache_key(&term, &storefront);
+  let now = unix_now_secs();
+  let cached = kv.get_search_cache_entry(&key).await?;
+
+  if let Some(entry) = &cached {
+    if entry.freshness(now, SEARCH_CACHE_CONTROL) == CacheFreshness::Fresh {
+      return Response::from_json(&entry.value);
+    }
+  }
+
+  let fetch_url = format!(
+    "https://itunes.apple.com/search?term={}&country={}&entity=software",
+    urlencoding::encode(term.trim()),
+    urlencoding::encode(&storefront)
+  );
+
+  match fetch_itunes_json(&fetch_url).await {
+    Ok(data) => {
+      let results = map_search_results(&data);
+      let is_negative = results.is_empty();
+      let entry = match cached {
+        Some(existing) => existing.revalidated(results.clone(), is_negative, now),
+        None => CacheEntry::new(results.clone(), is_negative, now),
+      };
+      kv.put_search_cache_entry(&key, &entry).await?;
+      Response::from_json(&results)
+    }
+    Err(e) => match cached {
+      Some(entry) => Response::from_json(&entry.value),
+      None => Err(e),
+    },
+  }
+}
@@ -1,11 +1,49 @@
-use asspp_core::download::new_task;
+use asspp_core::download::{self, new_task, RetryPolicy};
 use asspp_core::security::validate_download_url;
-use asspp_core::types::{CreateDownloadRequest, DownloadTask, TaskStatus};
+use asspp_core::types::{CreateDownloadRequest, DownloadTask, StagingUpload, TaskStatus, UploadedPart};
+use futures_util::StreamExt;
+use std::time::Duration;
 use worker::*;
 
 use crate::services::kv_metadata::KvMetadata;
 use crate::services::r2_storage::R2Storage;
 
+/// R2 requires multipart parts to be at least 5 MiB (except the last one).
+const MULTIPART_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Chunk size for the OTA manifest's `md5-size`/`md5s` integrity list.
+const MD5_CHUNK_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Whether to stream the plain (no-SINF) download path into an R2 multipart
+/// upload, or buffer the whole response like before. Streamed is the
+/// default; flip this for environments where memory is plentiful and the
+/// simpler single-`put` path is preferred.
+pub struct DownloadConfig {
+  pub stream_to_r2: bool,
+  /// Whether SINF injection goes through the hand-rolled single-pass rewriter
+  /// (`stream_inject::stream_inject`, see [`stream_inject_sinfs`]) instead of
+  /// the `zip`-crate-based [`inject_sinfs_in_memory`]. Defaults to `false`:
+  /// the rewriter is newer and hasn't had the in-memory path's mileage
+  /// against real-world IPAs yet.
+  ///
+  /// Despite the name, this is an algorithm swap, not a memory optimization:
+  /// `download_and_store` still reads the whole response into `bytes` before
+  /// either path runs (SINF injection needs a task's full `sinfs` resolved
+  /// up front, which the R2-streaming passthrough used for plain downloads
+  /// can't provide), so peak memory for a large IPA with SINFs is the same
+  /// either way this flag is set. See [`stream_inject_sinfs`]'s doc comment.
+  pub stream_sinf_injection: bool,
+}
+
+impl Default for DownloadConfig {
+  fn default() -> Self {
+    Self {
+      stream_to_r2: true,
+      stream_sinf_injection: false,
+    }
+  }
+}
+
 /// Create a download task. On Workers, the download is done via fetch()
 /// and the IPA is stored in R2.
 pub async fn create_task(
@@ -16,15 +54,26 @@ pub async fn create_task(
   let mut task = new_task(req);
 
   // Start download immediately
-  download_and_store(kv, r2, &mut task).await?;
+  download_and_store(kv, r2, &mut task, &DownloadConfig::default()).await?;
 
   Ok(task)
 }
 
+/// Resume a `Paused` download in place. Re-fetches `task.download_url`,
+/// sending a `Range` request when `task.downloaded_bytes > 0`, and continues
+/// the same flow `create_task` uses — appending to the in-progress R2
+/// multipart upload recorded in `task.staging_upload` when the server honors
+/// the range and `task.resume_etag` still matches, or falling back to a full
+/// restart otherwise.
+pub async fn resume_task(kv: &KvMetadata, r2: &R2Storage, task: &mut DownloadTask) -> Result<()> {
+  download_and_store(kv, r2, task, &DownloadConfig::default()).await
+}
+
 async fn download_and_store(
   kv: &KvMetadata,
   r2: &R2Storage,
   task: &mut DownloadTask,
+  config: &DownloadConfig,
 ) -> Result<()> {
   task.status = TaskStatus::Downloading;
   kv.put_task(task).await?;
@@ -33,15 +82,17 @@ async fn download_and_store(
   validate_download_url(&task.download_url)
     .map_err(|e| Error::RustError(e))?;
 
-  // Fetch the IPA from Apple CDN
-  let mut init = RequestInit::new();
-  init.method = Method::Get;
-  let request = Request::new_with_init(&task.download_url, &init)?;
-  let mut resp = Fetch::Request(request).send().await?;
+  // Fetch the IPA from Apple CDN, retrying transient failures. If we already
+  // have bytes staged from a previous attempt, this sends a `Range` request.
+  let mut resp = fetch_with_retry(task, kv, &RetryPolicy::default()).await?;
 
   if resp.status_code() >= 400 {
     task.status = TaskStatus::Failed;
-    task.error = Some("Download failed".into());
+    task.error = Some(format!(
+      "Download failed after {} attempt(s): HTTP {}",
+      task.retry_count + 1,
+      resp.status_code()
+    ));
     kv.put_task(task).await?;
     return Err(Error::RustError(format!(
       "HTTP {}",
@@ -49,34 +100,152 @@ async fn download_and_store(
     )));
   }
 
-  let bytes = resp.bytes().await?;
+  let expected_total = task
+    .software
+    .file_size_bytes
+    .as_ref()
+    .and_then(|s| s.parse::<u64>().ok());
+
+  if task.downloaded_bytes > 0 {
+    let status = resp.status_code();
+    let content_range = resp.headers().get("Content-Range")?;
+    let fetched_etag = resp.headers().get("ETag")?;
+    let decision = match expected_total {
+      Some(total) => {
+        download::evaluate_resume_response(status, content_range.as_deref(), total)
+          .map_err(Error::RustError)?
+      }
+      None if status == 206 => download::ResumeDecision::Resume,
+      None => download::ResumeDecision::RestartFromZero,
+    };
+    let identity_ok = download::resume_identity_matches(task.resume_etag.as_deref(), fetched_etag.as_deref());
+
+    if decision == download::ResumeDecision::RestartFromZero || !identity_ok {
+      // The server ignored our Range request, or the asset changed since we
+      // staged the earlier bytes — whatever's staged is no longer trustworthy.
+      task.downloaded_bytes = 0;
+      task.resume_etag = None;
+      task.staging_upload = None;
+      kv.put_task(task).await?;
+
+      resp = fetch_with_retry(task, kv, &RetryPolicy::default()).await?;
+      if resp.status_code() >= 400 {
+        task.status = TaskStatus::Failed;
+        task.error = Some(format!(
+          "Download failed after restart: HTTP {}",
+          resp.status_code()
+        ));
+        kv.put_task(task).await?;
+        return Err(Error::RustError(format!("HTTP {}", resp.status_code())));
+      }
+    }
+  }
+
+  // A pure pass-through stream is only safe when there's nothing to inject;
+  // SINF injection and Info.plist patching both still need the ZIP index, so
+  // they keep the buffered path.
+  if config.stream_to_r2 && task.sinfs.is_empty() && task.plist_overrides_xml.is_none() {
+    let staging_key = format!("staging/{}.ipa", task.id);
+    let (total_written, sha256) =
+      stream_passthrough_to_r2(r2, &staging_key, &mut resp, task, kv, expected_total).await?;
+
+    if let Err(e) = download::verify_expected_hash(task.expected_sha256.as_deref(), &sha256) {
+      let _ = r2.delete(&staging_key).await;
+      task.status = TaskStatus::Failed;
+      task.error = Some(e.clone());
+      kv.put_task(task).await?;
+      return Err(Error::RustError(e));
+    }
+
+    let blob_key = download::blob_key(&sha256);
+    if r2.size(&blob_key).await?.is_some() {
+      // Identical content already stored under this hash; drop our copy.
+      r2.delete(&staging_key).await?;
+    } else {
+      r2.copy(&staging_key, &blob_key).await?;
+      r2.delete(&staging_key).await?;
+    }
+
+    task.file_path = Some(blob_key.clone());
+    task.integrity = Some(asspp_core::types::IntegrityManifest {
+      sha256,
+      size: total_written,
+      injected_files: vec![],
+    });
+    task.md5_size = Some(MD5_CHUNK_SIZE);
+    task.md5s = Some(compute_chunked_md5(r2, &blob_key, total_written).await?);
+    task.status = TaskStatus::Completed;
+    task.progress = 100;
+    task.download_url = String::new();
+    task.sinfs = vec![];
+    task.itunes_metadata = None;
+    kv.put_task(task).await?;
+    return Ok(());
+  }
 
-  // SINF injection on Workers: done in-memory before storing
-  let r2_key = format!("packages/{}/{}/{}/{}.ipa",
-    task.account_hash,
-    task.software.bundle_id,
-    task.software.version,
-    task.id,
-  );
+  // This is the real memory ceiling for a task with SINFs or plist overrides:
+  // the whole response is buffered here regardless of which injector
+  // `DownloadConfig.stream_sinf_injection` picks below, since both need the
+  // complete archive to find and rewrite the handful of entries they touch.
+  let bytes = resp.bytes().await?;
 
-  let final_bytes = if !task.sinfs.is_empty() {
+  let (final_bytes, mut injected_files) = if !task.sinfs.is_empty() {
     task.status = TaskStatus::Injecting;
     kv.put_task(task).await?;
 
-    match inject_sinfs_in_memory(&bytes, &task.sinfs, task.itunes_metadata.as_deref()) {
-      Ok(modified) => modified,
+    let result = if config.stream_sinf_injection {
+      stream_inject_sinfs(&bytes, &task.sinfs, task.itunes_metadata.as_deref())
+    } else {
+      inject_sinfs_in_memory(&bytes, &task.sinfs, task.itunes_metadata.as_deref())
+    };
+
+    match result {
+      Ok((modified, injected)) => (modified, injected),
       Err(e) => {
         console_warn!("SINF injection failed: {}, storing without SINFs", e);
-        bytes
+        (bytes, vec![])
       }
     }
   } else {
-    bytes
+    (bytes, vec![])
   };
 
-  // Store IPA in R2 (single write)
-  r2.put(&r2_key, final_bytes).await?;
-  task.file_path = Some(r2_key);
+  let final_bytes = if let Some(overrides_xml) = &task.plist_overrides_xml {
+    match apply_plist_overrides(&final_bytes, overrides_xml) {
+      Ok(patched) => {
+        injected_files.push("Info.plist".to_string());
+        patched
+      }
+      Err(e) => {
+        task.status = TaskStatus::Failed;
+        task.error = Some(e.clone());
+        kv.put_task(task).await?;
+        return Err(Error::RustError(e));
+      }
+    }
+  } else {
+    final_bytes
+  };
+
+  let integrity = asspp_core::integrity::build_manifest(&final_bytes, &injected_files);
+
+  if let Err(e) = download::verify_expected_hash(task.expected_sha256.as_deref(), &integrity.sha256) {
+    task.status = TaskStatus::Failed;
+    task.error = Some(e.clone());
+    kv.put_task(task).await?;
+    return Err(Error::RustError(e));
+  }
+
+  // Content-address the blob; skip the write entirely if it's already there.
+  let blob_key = download::blob_key(&integrity.sha256);
+  let total_size = integrity.size;
+  if r2.size(&blob_key).await?.is_none() {
+    r2.put(&blob_key, final_bytes).await?;
+  }
+  task.file_path = Some(blob_key.clone());
+  task.integrity = Some(integrity);
+  task.md5_size = Some(MD5_CHUNK_SIZE);
+  task.md5s = Some(compute_chunked_md5(r2, &blob_key, total_size).await?);
 
   // Mark completed and strip secrets
   task.status = TaskStatus::Completed;
@@ -84,16 +253,380 @@ async fn download_and_store(
   task.download_url = String::new();
   task.sinfs = vec![];
   task.itunes_metadata = None;
+  task.plist_overrides_xml = None;
   kv.put_task(task).await?;
 
   Ok(())
 }
 
+/// Fetch `task.download_url`, retrying transient failures (408/429/5xx and
+/// network errors) per `policy`. Records the running attempt count and last
+/// error on the task so callers can see why a download took a while; gives
+/// up and returns the last outcome once the policy's retries are exhausted.
+async fn fetch_with_retry(
+  task: &mut DownloadTask,
+  kv: &KvMetadata,
+  policy: &RetryPolicy,
+) -> Result<Response> {
+  let mut retries: u32 = 0;
+  task.retry_count = 0;
+
+  loop {
+    let mut init = RequestInit::new();
+    init.method = Method::Get;
+    if task.downloaded_bytes > 0 {
+      let headers = Headers::new();
+      headers.set("Range", &download::range_header(task.downloaded_bytes))?;
+      init.headers = headers;
+    }
+    let request = Request::new_with_init(&task.download_url, &init)?;
+
+    let outcome = Fetch::Request(request).send().await;
+    let retry_reason = match &outcome {
+      Ok(resp) if download::is_retryable_status(resp.status_code()) => {
+        Some(format!("HTTP {}", resp.status_code()))
+      }
+      Ok(_) => return outcome,
+      Err(e) => Some(e.to_string()),
+    };
+
+    if !download::should_retry(retries, policy) {
+      return outcome;
+    }
+
+    retries += 1;
+    task.retry_count = retries;
+    task.error = retry_reason;
+    kv.put_task(task).await?;
+
+    let jitter_fraction = if policy.jitter { js_sys::Math::random() } else { 0.0 };
+    let delay_secs = download::delay_for_attempt(policy, retries, jitter_fraction);
+    Delay::from(Duration::from_millis((delay_secs * 1000.0) as u64)).await;
+  }
+}
+
+/// Compute the OTA manifest's chunked-MD5 integrity list for a completed
+/// package: read the R2 object back in fixed-size blocks (rather than
+/// loading the whole IPA into memory) and MD5 each one, in order, starting
+/// at offset 0. Run once at package-completion time so the manifest is
+/// deterministic across requests instead of being recomputed per request.
+async fn compute_chunked_md5(r2: &R2Storage, blob_key: &str, total_size: u64) -> Result<Vec<String>> {
+  let mut digests = Vec::new();
+  let mut offset: u64 = 0;
+
+  while offset < total_size {
+    let len = MD5_CHUNK_SIZE.min(total_size - offset);
+    let block = r2
+      .get_range(blob_key, offset, len)
+      .await?
+      .ok_or_else(|| Error::RustError(format!("missing range at offset {offset} in {blob_key}")))?;
+    digests.push(asspp_core::integrity::md5_hex(&block));
+    offset += len;
+  }
+
+  Ok(digests)
+}
+
+/// Find the app icon inside a completed package, convert it from Apple's
+/// CgBI format to a standard PNG, and cache the converted bytes in R2 under
+/// `icons/{task-id}.png` so both icon endpoints only pay the extraction cost
+/// once. Returns `None` if the task has no stored package or no icon could
+/// be resolved/converted; callers fall back to the placeholder in that case.
+pub async fn get_or_convert_icon(r2: &R2Storage, task: &DownloadTask) -> Result<Option<Vec<u8>>> {
+  let icon_key = format!("icons/{}.png", task.id);
+  if let Some(cached) = r2.get(&icon_key).await? {
+    return Ok(Some(cached));
+  }
+
+  let Some(blob_key) = task.file_path.clone() else {
+    return Ok(None);
+  };
+  let Some(ipa_data) = r2.get(&blob_key).await? else {
+    return Ok(None);
+  };
+
+  let Some(png) = extract_icon_png(&ipa_data) else {
+    return Ok(None);
+  };
+
+  r2.put(&icon_key, png.clone()).await?;
+  Ok(Some(png))
+}
+
+/// Locate `Payload/<App>.app/Info.plist`, resolve its icon file names, pick
+/// the largest matching PNG entry in the archive, and deoptimize it from
+/// CgBI if needed.
+fn extract_icon_png(ipa_data: &[u8]) -> Option<Vec<u8>> {
+  use asspp_core::{cgbi, plist_util, sinf};
+  use std::io::{Cursor, Read};
+
+  let reader = Cursor::new(ipa_data);
+  let mut zip = zip::ZipArchive::new(reader).ok()?;
+
+  let mut app_dir = None;
+  let mut info_plist_bytes = Vec::new();
+  for i in 0..zip.len() {
+    let name = zip.by_index_raw(i).ok()?.name().to_string();
+    if sinf::is_info_plist(&name) {
+      let dir = name.split("/Info.plist").next()?.to_string();
+      let mut entry = zip.by_index(i).ok()?;
+      entry.read_to_end(&mut info_plist_bytes).ok()?;
+      app_dir = Some(dir);
+      break;
+    }
+  }
+  let app_dir = app_dir?;
+  let info_plist = plist_util::parse_plist(&info_plist_bytes)?;
+  let icon_names = plist_util::resolve_icon_file_names(&info_plist);
+  if icon_names.is_empty() {
+    return None;
+  }
+
+  let prefix = format!("{}/", app_dir);
+  let mut best: Option<(usize, usize)> = None; // (zip index, uncompressed size)
+  for i in 0..zip.len() {
+    let entry = zip.by_index_raw(i).ok()?;
+    let name = entry.name();
+    if !name.starts_with(&prefix) || !name.ends_with(".png") {
+      continue;
+    }
+    let stem = name
+      .rsplit('/')
+      .next()
+      .unwrap_or(name)
+      .trim_end_matches(".png");
+    let matches = icon_names.iter().any(|candidate| {
+      let candidate = candidate.trim_end_matches(".png");
+      stem == candidate || stem.starts_with(&format!("{candidate}@"))
+    });
+    if !matches {
+      continue;
+    }
+    let size = entry.size() as usize;
+    if best.map(|(_, best_size)| size > best_size).unwrap_or(true) {
+      best = Some((i, size));
+    }
+  }
+
+  let (best_index, _) = best?;
+  let mut entry = zip.by_index(best_index).ok()?;
+  let mut png_bytes = Vec::new();
+  entry.read_to_end(&mut png_bytes).ok()?;
+
+  cgbi::deoptimize_cgbi_png(&png_bytes)
+}
+
+/// Verify a completed task's stored bytes against its recorded manifest,
+/// marking the task `Failed` in KV if the package no longer matches.
+pub async fn verify_integrity(
+  kv: &KvMetadata,
+  task: &mut DownloadTask,
+  data: &[u8],
+) -> Result<()> {
+  let Some(manifest) = task.integrity.clone() else {
+    return Ok(());
+  };
+
+  if let Err(e) = asspp_core::integrity::verify(data, &manifest) {
+    task.status = TaskStatus::Failed;
+    task.error = Some(format!("Integrity check failed: {e}"));
+    kv.put_task(task).await?;
+    return Err(Error::RustError(e.to_string()));
+  }
+
+  Ok(())
+}
+
+/// Stream a fetch response body straight into an R2 multipart upload instead
+/// of buffering the whole IPA in memory first. `task.downloaded_bytes` and
+/// `task.staging_upload` (the multipart upload's id and committed parts) are
+/// persisted to KV after every part, so a Worker invocation that gets cut
+/// off mid-stream can be picked back up later by `resume_task` appending to
+/// the same upload instead of restarting the transfer from byte 0.
+///
+/// The SHA-256 for the integrity manifest is computed in a second chunked
+/// pass over the completed object (`compute_sha256_from_r2`) rather than
+/// incrementally here: an in-progress multipart upload can't be read back,
+/// so there would be nowhere to resume a partial hash from across
+/// invocations; hashing the finished object once is simpler and always
+/// correct regardless of how many attempts the transfer took.
+/// Returns `(total bytes written, sha256 hex digest)`.
+async fn stream_passthrough_to_r2(
+  r2: &R2Storage,
+  r2_key: &str,
+  resp: &mut Response,
+  task: &mut DownloadTask,
+  kv: &KvMetadata,
+  expected_total: Option<u64>,
+) -> Result<(u64, String)> {
+  if task.resume_etag.is_none() {
+    task.resume_etag = resp.headers().get("ETag")?;
+  }
+
+  let (multipart, mut part_number, mut total_written, mut committed) = match task.staging_upload.take() {
+    Some(staging) => {
+      let handle = r2.resume_multipart_upload(r2_key, &staging.upload_id).await?;
+      let next_part = staging.parts.len() as u16 + 1;
+      (handle, next_part, task.downloaded_bytes, staging.parts)
+    }
+    None => (r2.create_multipart_upload(r2_key).await?, 1u16, 0u64, Vec::new()),
+  };
+
+  let mut body = resp.stream()?;
+  let mut buffer = Vec::with_capacity(MULTIPART_PART_SIZE);
+
+  while let Some(chunk) = body.next().await {
+    let chunk = chunk?;
+    buffer.extend_from_slice(&chunk);
+    total_written += chunk.len() as u64;
+
+    if buffer.len() >= MULTIPART_PART_SIZE {
+      let part = multipart
+        .upload_part(part_number, std::mem::take(&mut buffer))
+        .await?;
+      committed.push(UploadedPart {
+        part_number,
+        etag: part.etag,
+      });
+      part_number += 1;
+
+      task.downloaded_bytes = total_written;
+      task.staging_upload = Some(StagingUpload {
+        upload_id: multipart.upload_id().to_string(),
+        parts: committed.clone(),
+      });
+      if let Some(total) = expected_total {
+        task.progress = ((total_written * 100) / total.max(1)).min(100) as u8;
+      }
+      kv.put_task(task).await?;
+    }
+  }
+
+  if !buffer.is_empty() {
+    let part = multipart.upload_part(part_number, buffer).await?;
+    committed.push(UploadedPart {
+      part_number,
+      etag: part.etag,
+    });
+  }
+
+  multipart.complete(committed).await?;
+  task.staging_upload = None;
+  task.downloaded_bytes = total_written;
+
+  let sha256 = compute_sha256_from_r2(r2, r2_key, total_written).await?;
+
+  Ok((total_written, sha256))
+}
+
+/// Read a completed R2 object back in fixed-size blocks and hash it, the same
+/// way `compute_chunked_md5` computes the OTA manifest's digests, instead of
+/// hashing incrementally during the upload (see `stream_passthrough_to_r2`).
+async fn compute_sha256_from_r2(r2: &R2Storage, blob_key: &str, total_size: u64) -> Result<String> {
+  use asspp_core::integrity::{hex_encode, Sha256};
+
+  let mut hasher = Sha256::new();
+  let mut offset: u64 = 0;
+
+  while offset < total_size {
+    let len = MD5_CHUNK_SIZE.min(total_size - offset);
+    let block = r2
+      .get_range(blob_key, offset, len)
+      .await?
+      .ok_or_else(|| Error::RustError(format!("missing range at offset {offset} in {blob_key}")))?;
+    hasher.update(&block);
+    offset += len;
+  }
+
+  Ok(hex_encode(hasher.finalize()))
+}
+
+/// Rebrand or correct a packaged app's identity before it's served: apply an
+/// overlay plist (e.g. a new `CFBundleIdentifier`/`CFBundleVersion`/
+/// `CFBundleDisplayName`, or replacement `CFBundleURLTypes`) onto
+/// `Payload/*.app/Info.plist` and rewrite that zip entry as a binary plist.
+/// Everything else in the archive is copied through untouched.
+pub fn patch_info_plist(ipa_data: &[u8], overrides: &plist::Value) -> std::result::Result<Vec<u8>, String> {
+  use asspp_core::{plist_util, sinf};
+  use std::io::{Cursor, Read, Write};
+
+  let reader = Cursor::new(ipa_data);
+  let mut zip = zip::ZipArchive::new(reader).map_err(|e| format!("Read ZIP: {}", e))?;
+
+  let mut info_plist_name = None;
+  for i in 0..zip.len() {
+    let name = zip
+      .by_index_raw(i)
+      .map_err(|e| format!("Read entry: {}", e))?
+      .name()
+      .to_string();
+    if sinf::is_info_plist(&name) {
+      info_plist_name = Some(name);
+      break;
+    }
+  }
+  let info_plist_name = info_plist_name.ok_or_else(|| "Could not find Info.plist".to_string())?;
+
+  let mut info_plist_bytes = Vec::new();
+  zip
+    .by_name(&info_plist_name)
+    .map_err(|e| format!("Read Info.plist: {}", e))?
+    .read_to_end(&mut info_plist_bytes)
+    .map_err(|e| format!("Read Info.plist: {}", e))?;
+
+  let mut info_plist = plist_util::parse_plist(&info_plist_bytes)
+    .ok_or_else(|| "Could not parse Info.plist".to_string())?;
+  plist_util::merge(&mut info_plist, overrides);
+
+  let mut patched_plist = Vec::new();
+  plist::to_writer_binary(&mut patched_plist, &info_plist)
+    .map_err(|e| format!("Write binary plist: {}", e))?;
+
+  let mut out_buf = Vec::new();
+  {
+    let mut out_zip = zip::ZipWriter::new(Cursor::new(&mut out_buf));
+
+    for i in 0..zip.len() {
+      let entry = zip
+        .by_index_raw(i)
+        .map_err(|e| format!("Read entry: {}", e))?;
+      if entry.name() == info_plist_name {
+        continue;
+      }
+      out_zip
+        .raw_copy_file(entry)
+        .map_err(|e| format!("Copy: {}", e))?;
+    }
+
+    let options = zip::write::SimpleFileOptions::default()
+      .compression_method(zip::CompressionMethod::Stored);
+    out_zip
+      .start_file(&info_plist_name, options)
+      .map_err(|e| format!("Start: {}", e))?;
+    out_zip
+      .write_all(&patched_plist)
+      .map_err(|e| format!("Write: {}", e))?;
+    out_zip.finish().map_err(|e| format!("Finish: {}", e))?;
+  }
+
+  Ok(out_buf)
+}
+
+/// Parse `overrides_xml` as a plist and apply it to `ipa_data` via
+/// [`patch_info_plist`]. Split out so `download_and_store` can carry the
+/// override as the plain XML string it arrived as on `DownloadTask` and only
+/// pay for parsing it once it's actually about to be applied.
+fn apply_plist_overrides(ipa_data: &[u8], overrides_xml: &str) -> std::result::Result<Vec<u8>, String> {
+  let overrides = asspp_core::plist_util::parse_plist(overrides_xml.as_bytes())
+    .ok_or_else(|| "Could not parse plist overrides".to_string())?;
+  patch_info_plist(ipa_data, &overrides)
+}
+
 fn inject_sinfs_in_memory(
   ipa_data: &[u8],
   sinfs: &[asspp_core::types::Sinf],
   itunes_metadata_b64: Option<&str>,
-) -> std::result::Result<Vec<u8>, String> {
+) -> std::result::Result<(Vec<u8>, Vec<String>), String> {
   use asspp_core::plist_util;
   use asspp_core::sinf::{self, InjectionSource};
   use base64::Engine;
@@ -199,9 +732,11 @@ fn inject_sinfs_in_memory(
   let plan = sinf::plan_injection(&bundle_name, &source, &sinf_data, metadata_binary.as_deref());
 
   if plan.files.is_empty() {
-    return Ok(ipa_data.to_vec());
+    return Ok((ipa_data.to_vec(), vec![]));
   }
 
+  let injected_files: Vec<String> = plan.files.iter().map(|(path, _)| path.clone()).collect();
+
   // Rewrite ZIP in memory
   let mut out_buf = Vec::new();
   {
@@ -235,5 +770,130 @@ fn inject_sinfs_in_memory(
     out_zip.finish().map_err(|e| format!("Finish: {}", e))?;
   }
 
-  Ok(out_buf)
+  Ok((out_buf, injected_files))
+}
+
+/// Same job as [`inject_sinfs_in_memory`], but rewrites the archive with
+/// `stream_inject::stream_inject` — a hand-rolled single-pass reader/writer —
+/// instead of the `zip` crate's `ZipArchive`/`ZipWriter`. Selected via
+/// `DownloadConfig.stream_sinf_injection`.
+///
+/// The source bytes still have to be fully resident in memory by the time
+/// this runs (the network fetch itself isn't re-architected here — see
+/// `download_and_store`'s `config.stream_to_r2 && task.sinfs.is_empty()`
+/// gate, which is what actually avoids buffering a download), and the
+/// rewritten output is built as one `Vec<u8>` before it reaches the same
+/// `r2.put` call the in-memory path uses. So this isn't a memory win over
+/// `inject_sinfs_in_memory`; what it buys is dropping the `zip` crate's
+/// random-access archive index from this path in favor of the narrower,
+/// auditable forward-only reader/writer in `stream_inject.rs`, and gives
+/// `stream_inject` itself (previously dead code outside its own tests) a
+/// real caller.
+fn stream_inject_sinfs(
+  ipa_data: &[u8],
+  sinfs: &[asspp_core::types::Sinf],
+  itunes_metadata_b64: Option<&str>,
+) -> std::result::Result<(Vec<u8>, Vec<String>), String> {
+  use asspp_core::cgbi;
+  use asspp_core::plist_util;
+  use asspp_core::sinf::{self, InjectionSource};
+  use asspp_core::stream_inject;
+  use base64::Engine;
+  use std::collections::{HashMap, HashSet};
+  use std::io::Cursor;
+
+  let (cd_offset, cd_size, _count) = stream_inject::find_end_of_central_directory(ipa_data)
+    .map_err(|e| format!("Find central directory: {e}"))?;
+  let cd_start = cd_offset as usize;
+  let cd_end = cd_start
+    .checked_add(cd_size as usize)
+    .filter(|&end| end <= ipa_data.len())
+    .ok_or_else(|| "Central directory runs past end of archive".to_string())?;
+  let entries = stream_inject::parse_central_directory(&ipa_data[cd_start..cd_end])
+    .map_err(|e| format!("Parse central directory: {e}"))?;
+
+  let bundle_name = entries
+    .iter()
+    .find_map(|e| sinf::extract_bundle_name(&e.name))
+    .ok_or_else(|| "Could not read bundle name".to_string())?;
+
+  let manifest_name = entries.iter().find(|e| sinf::is_manifest_plist(&e.name)).map(|e| e.name.clone());
+  let info_name = entries.iter().find(|e| sinf::is_info_plist(&e.name)).map(|e| e.name.clone());
+
+  let mut wanted: HashSet<&str> = HashSet::new();
+  if let Some(name) = manifest_name.as_deref() {
+    wanted.insert(name);
+  }
+  if let Some(name) = info_name.as_deref() {
+    wanted.insert(name);
+  }
+  if wanted.is_empty() {
+    return Err("Could not read manifest or info plist".to_string());
+  }
+
+  let mut scan = Cursor::new(&ipa_data[..cd_start]);
+  let payloads = stream_inject::read_selected_entries(&mut scan, &entries, &wanted)
+    .map_err(|e| format!("Read manifest/info plist: {e}"))?;
+  let meta_by_name: HashMap<&str, &stream_inject::ZipEntryMeta> =
+    entries.iter().map(|e| (e.name.as_str(), e)).collect();
+
+  let decode_entry = |name: &str| -> Option<Vec<u8>> {
+    let raw = payloads.get(name)?;
+    match meta_by_name.get(name)?.compression_method {
+      0 => Some(raw.clone()),
+      8 => cgbi::inflate_raw(raw).ok(),
+      _ => None,
+    }
+  };
+
+  let source = manifest_name
+    .as_deref()
+    .and_then(decode_entry)
+    .and_then(|buf| plist_util::parse_plist(&buf))
+    .and_then(|val| plist_util::get_string_array(&val, "SinfPaths"))
+    .map(|sinf_paths| InjectionSource::Manifest { sinf_paths })
+    .or_else(|| {
+      info_name.as_deref().and_then(decode_entry).and_then(|buf| {
+        let val = plist_util::parse_plist(&buf)?;
+        let bundle_executable = plist_util::get_string(&val, "CFBundleExecutable")?;
+        Some(InjectionSource::Info { bundle_executable })
+      })
+    })
+    .ok_or_else(|| "Could not read manifest or info plist".to_string())?;
+
+  let sinf_data: Vec<(i64, Vec<u8>)> = sinfs
+    .iter()
+    .map(|s| {
+      let data = base64::engine::general_purpose::STANDARD
+        .decode(&s.sinf)
+        .map_err(|e| format!("Decode sinf: {}", e))?;
+      Ok((s.id, data))
+    })
+    .collect::<std::result::Result<Vec<_>, String>>()?;
+
+  let metadata_binary = if let Some(b64) = itunes_metadata_b64 {
+    let xml_bytes = base64::engine::general_purpose::STANDARD
+      .decode(b64)
+      .map_err(|e| format!("Decode metadata: {}", e))?;
+    let xml_str = String::from_utf8_lossy(&xml_bytes);
+    match plist_util::xml_to_binary_plist(&xml_str) {
+      Ok(binary) => Some(binary),
+      Err(_) => Some(xml_bytes),
+    }
+  } else {
+    None
+  };
+
+  let plan = sinf::plan_injection(&bundle_name, &source, &sinf_data, metadata_binary.as_deref());
+  if plan.files.is_empty() {
+    return Ok((ipa_data.to_vec(), vec![]));
+  }
+  let injected_files: Vec<String> = plan.files.iter().map(|(path, _)| path.clone()).collect();
+
+  let mut source_reader = Cursor::new(&ipa_data[..cd_start]);
+  let mut out = Vec::with_capacity(ipa_data.len());
+  stream_inject::stream_inject(&mut source_reader, &entries, &plan, &mut out)
+    .map_err(|e| format!("Stream inject: {e}"))?;
+
+  Ok((out, injected_files))
 }